@@ -1,12 +1,15 @@
 use std::{fs, io, path::PathBuf, sync::Arc};
 
+use crate::error::{Error, Result};
+
 use clap::{self, ArgAction, Parser};
 use directories::BaseDirs;
+use encoding_rs::{Encoding, WINDOWS_1252};
 use parking_lot::{
     lock_api::{RwLockReadGuard, RwLockWriteGuard},
     RawRwLock, RwLock,
 };
-use serde::{Deserialize, Serialize};
+use serde::{de, Deserialize, Deserializer, Serialize};
 
 #[derive(Parser)]
 pub struct CmdConfig {
@@ -27,22 +30,35 @@ pub struct CmdConfig {
     /// overwrites the selected config with default values
     #[arg(long, action=ArgAction::SetTrue)]
     generate_config: bool,
+    /// draw on the main screen instead of the alternate screen
+    #[arg(long, action=ArgAction::SetTrue)]
+    no_alternate_screen: bool,
+    /// open the files that were open when the editor was last closed
+    #[arg(long, action=ArgAction::SetTrue)]
+    restore: bool,
 }
 
 impl CmdConfig {
-    pub fn check_actions(&self) -> io::Result<bool> {
+    pub fn check_actions(&self) -> Result<bool> {
         if self.generate_config {
-            println!("{}", &self.config.display());
+            println!("{}", self.config.display());
             if let Some(p) = self.config.ancestors().take(2).last() {
                 println!("{}", p.display());
-                fs::create_dir_all(p)?;
+                fs::create_dir_all(p).map_err(|source| Error::ConfigWrite {
+                    path: p.to_owned(),
+                    source,
+                })?;
             }
             // let mut f = File::create(&self.config)?;
             println!("created file");
             std::fs::write(
                 &self.config,
                 toml::to_string(&FileConfig::default()).unwrap().as_bytes(),
-            )?;
+            )
+            .map_err(|source| Error::ConfigWrite {
+                path: self.config.to_owned(),
+                source,
+            })?;
 
             return Ok(true);
         }
@@ -52,39 +68,145 @@ impl CmdConfig {
 }
 
 #[derive(Deserialize, Serialize)]
+#[serde(default)]
 struct FileConfig {
     tab_size: usize,
+    /// indent and outdent with tabs instead of `tab_size` spaces, tabs in files are kept
+    use_tabs: bool,
+    /// wrap redraws in synchronized update escapes, terminals without support ignore them
+    synchronized_update: bool,
+    /// encoding label used for files that are neither UTF-8 nor UTF-16
+    #[serde(deserialize_with = "encoding_label")]
+    legacy_encoding: String,
+    /// draw on the alternate screen so the terminal contents come back on exit
+    alternate_screen: bool,
+    /// characters besides letters and digits that are part of a word for Ctrl+arrows
+    word_chars: String,
+    /// opening and closing characters typed together, one pair after another, empty turns it off
+    auto_pairs: String,
+}
+
+fn encoding_label<'de, D: Deserializer<'de>>(d: D) -> std::result::Result<String, D::Error> {
+    let label = String::deserialize(d)?;
+    if Encoding::for_label(label.as_bytes()).is_none() {
+        return Err(de::Error::custom(format!("unknown encoding \"{}\"", label)));
+    }
+    Ok(label)
 }
 
 impl Default for FileConfig {
     fn default() -> Self {
-        Self { tab_size: 4 }
+        Self {
+            tab_size: 4,
+            use_tabs: false,
+            synchronized_update: true,
+            legacy_encoding: WINDOWS_1252.name().to_owned(),
+            alternate_screen: true,
+            word_chars: "_".to_owned(),
+            auto_pairs: "()[]{}\"\"".to_owned(),
+        }
     }
 }
 
 #[derive(Debug, Clone)]
 pub struct Config {
     pub file: Option<PathBuf>,
+    #[allow(unused)]
     pub config_path: PathBuf,
     pub tab_size: usize,
+    pub use_tabs: bool,
+    pub synchronized_update: bool,
+    pub legacy_encoding: &'static Encoding,
+    pub alternate_screen: bool,
+    pub word_chars: String,
+    /// (opening, closing) characters inserted together
+    pub auto_pairs: Vec<(char, char)>,
+    /// bring back the buffers of the last session on start
+    pub restore: bool,
 }
-impl From<CmdConfig> for Config {
-    fn from(value: CmdConfig) -> Self {
-        let fc = toml::from_str(&fs::read_to_string(value.config.to_owned()).unwrap_or("".into()))
-            .unwrap_or(FileConfig::default());
-        Self::merge(value, fc)
+impl Default for Config {
+    fn default() -> Self {
+        let cmd = CmdConfig {
+            file: None,
+            config: PathBuf::new(),
+            generate_config: false,
+            no_alternate_screen: false,
+            restore: false,
+        };
+        Self::merge(cmd, FileConfig::default())
     }
 }
 impl Config {
+    /// read the config file named on the command line, a missing file means default values
+    pub fn load(cmd: CmdConfig) -> Result<Config> {
+        let text = match fs::read_to_string(&cmd.config) {
+            Ok(t) => t,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(source) => {
+                return Err(Error::ConfigRead {
+                    path: cmd.config,
+                    source,
+                })
+            }
+        };
+        let fc = toml::from_str(&text).map_err(|e| {
+            let offset = e.span().map(|s| s.start).unwrap_or(0);
+            Error::config_parse(cmd.config.to_owned(), &text, offset, e.message())
+        })?;
+        Ok(Self::merge(cmd, fc))
+    }
     fn merge(cmd: CmdConfig, f: FileConfig) -> Config {
         Self {
             file: cmd.file,
             config_path: cmd.config,
             tab_size: f.tab_size,
+            use_tabs: f.use_tabs,
+            synchronized_update: f.synchronized_update,
+            legacy_encoding: Encoding::for_label(f.legacy_encoding.as_bytes())
+                .unwrap_or(WINDOWS_1252),
+            alternate_screen: f.alternate_screen && !cmd.no_alternate_screen,
+            word_chars: f.word_chars,
+            auto_pairs: pairs(&f.auto_pairs),
+            restore: cmd.restore,
+        }
+    }
+    /// Change an option for this session from the command line, `None` shows its value.
+    /// Returns the text to show.
+    pub fn set(&mut self, name: &str, value: Option<&str>) -> Result<Option<String>> {
+        let unknown = || Error::Command(format!("unknown option: {}", name));
+        let Some(v) = value else {
+            let value = match name {
+                "tab_size" => self.tab_size.to_string(),
+                "use_tabs" => self.use_tabs.to_string(),
+                "word_chars" => self.word_chars.clone(),
+                "auto_pairs" => self.auto_pairs.iter().flat_map(|(a, b)| [a, b]).collect(),
+                "legacy_encoding" => self.legacy_encoding.name().to_owned(),
+                _ => return Err(unknown()),
+            };
+            return Ok(Some(format!("{}={}", name, value)));
+        };
+        let invalid = || Error::Command(format!("invalid value for {}: {}", name, v));
+        match name {
+            "tab_size" => self.tab_size = v.parse().ok().filter(|n| *n > 0).ok_or_else(invalid)?,
+            "use_tabs" => self.use_tabs = v.parse().map_err(|_| invalid())?,
+            "word_chars" => self.word_chars = v.to_owned(),
+            "auto_pairs" if v.chars().count() % 2 == 0 => self.auto_pairs = pairs(v),
+            "auto_pairs" => return Err(invalid()),
+            "legacy_encoding" => {
+                self.legacy_encoding = Encoding::for_label(v.as_bytes()).ok_or_else(invalid)?
+            }
+            _ => return Err(unknown()),
         }
+        Ok(None)
     }
 }
 
+/// `"()[]"` as `[('(', ')'), ('[', ']')]`, a last unpaired character is dropped
+fn pairs(text: &str) -> Vec<(char, char)> {
+    let chars: Vec<char> = text.chars().collect();
+    chars.chunks_exact(2).map(|p| (p[0], p[1])).collect()
+}
+
 #[derive(Debug, Clone)]
 pub struct SharedConfig {
     config: Arc<RwLock<Config>>,
@@ -95,10 +217,10 @@ impl SharedConfig {
             config: Arc::new(RwLock::new(conf)),
         }
     }
-    pub fn read(&self) -> RwLockReadGuard<RawRwLock, Config> {
+    pub fn read(&self) -> RwLockReadGuard<'_, RawRwLock, Config> {
         self.config.read()
     }
-    pub fn write(&self) -> RwLockWriteGuard<RawRwLock, Config> {
+    pub fn write(&self) -> RwLockWriteGuard<'_, RawRwLock, Config> {
         self.config.write()
     }
 }
@@ -0,0 +1,156 @@
+//! Every action the palette offers, each one runs by pressing the key it is bound to so
+//! the palette and the shortcuts can't disagree.
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use crate::{data::ListItem, macros::key_name};
+
+pub struct Command {
+    pub name: &'static str,
+    pub key: KeyEvent,
+}
+
+const fn ctrl(c: char) -> KeyEvent {
+    KeyEvent::new(KeyCode::Char(c), KeyModifiers::CONTROL)
+}
+const fn ctrl_shift(c: char) -> KeyEvent {
+    KeyEvent::new(
+        KeyCode::Char(c),
+        KeyModifiers::CONTROL.union(KeyModifiers::SHIFT),
+    )
+}
+const fn ctrl_key(code: KeyCode) -> KeyEvent {
+    KeyEvent::new(code, KeyModifiers::CONTROL)
+}
+const fn alt(code: KeyCode) -> KeyEvent {
+    KeyEvent::new(code, KeyModifiers::ALT)
+}
+const fn ctrl_alt(code: KeyCode) -> KeyEvent {
+    KeyEvent::new(code, KeyModifiers::CONTROL.union(KeyModifiers::ALT))
+}
+
+pub static COMMANDS: &[Command] = &[
+    Command {
+        name: "Save",
+        key: ctrl('s'),
+    },
+    Command {
+        name: "Open file",
+        key: ctrl('o'),
+    },
+    Command {
+        name: "Find file",
+        key: ctrl('p'),
+    },
+    Command {
+        name: "Toggle file explorer",
+        key: ctrl('b'),
+    },
+    Command {
+        name: "Next buffer",
+        key: ctrl_key(KeyCode::PageDown),
+    },
+    Command {
+        name: "Previous buffer",
+        key: ctrl_key(KeyCode::PageUp),
+    },
+    Command {
+        name: "Search",
+        key: ctrl('f'),
+    },
+    Command {
+        name: "Grep project",
+        key: ctrl_shift('F'),
+    },
+    Command {
+        name: "Re-open with encoding",
+        key: ctrl('e'),
+    },
+    Command {
+        name: "Undo",
+        key: ctrl('z'),
+    },
+    Command {
+        name: "Redo",
+        key: ctrl('y'),
+    },
+    Command {
+        name: "Copy",
+        key: ctrl('c'),
+    },
+    Command {
+        name: "Cut",
+        key: ctrl('x'),
+    },
+    Command {
+        name: "Paste",
+        key: ctrl('v'),
+    },
+    Command {
+        name: "Duplicate lines",
+        key: ctrl_shift('D'),
+    },
+    Command {
+        name: "Delete lines",
+        key: ctrl('k'),
+    },
+    Command {
+        name: "Join lines",
+        key: ctrl('j'),
+    },
+    Command {
+        name: "Move lines up",
+        key: alt(KeyCode::Up),
+    },
+    Command {
+        name: "Move lines down",
+        key: alt(KeyCode::Down),
+    },
+    Command {
+        name: "Toggle comment",
+        key: ctrl('/'),
+    },
+    Command {
+        name: "Go to matching bracket",
+        key: ctrl(']'),
+    },
+    Command {
+        name: "Select next occurrence",
+        key: ctrl('d'),
+    },
+    Command {
+        name: "Add cursor above",
+        key: ctrl_alt(KeyCode::Up),
+    },
+    Command {
+        name: "Add cursor below",
+        key: ctrl_alt(KeyCode::Down),
+    },
+    Command {
+        name: "Command line",
+        key: ctrl('g'),
+    },
+    Command {
+        name: "Record macro",
+        key: ctrl('r'),
+    },
+    Command {
+        name: "Play macro",
+        key: ctrl('t'),
+    },
+    Command {
+        name: "Quit",
+        key: ctrl('q'),
+    },
+];
+
+/// the commands as palette entries, in the order of `COMMANDS`
+pub fn list_items() -> Vec<ListItem> {
+    COMMANDS
+        .iter()
+        .map(|c| ListItem {
+            label: c.name.to_owned(),
+            detail: key_name(&c.key).unwrap_or_default(),
+        })
+        .collect()
+}
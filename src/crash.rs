@@ -0,0 +1,127 @@
+use std::{
+    backtrace::Backtrace,
+    fs,
+    io::{self, Write},
+    panic::{self, PanicHookInfo},
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+    time::Duration,
+};
+
+use crate::{
+    data::{FileData, FileStatus, SharedData},
+    state, terminal,
+};
+
+static PANICKED: AtomicBool = AtomicBool::new(false);
+
+/// Files written for the current crash.
+#[derive(Debug, Default, Clone)]
+pub struct CrashFiles {
+    pub report: Option<PathBuf>,
+    pub rescue: Option<PathBuf>,
+    /// the buffer was handled, either written to `rescue` or had nothing unsaved
+    pub rescued: bool,
+    pub rescue_error: Option<String>,
+}
+static FILES: Mutex<CrashFiles> = Mutex::new(CrashFiles {
+    report: None,
+    rescue: None,
+    rescued: false,
+    rescue_error: None,
+});
+
+/// true once any thread panicked, the renderer stops drawing over the restored terminal
+pub fn panicked() -> bool {
+    PANICKED.load(Ordering::SeqCst)
+}
+
+/// Install a panic hook that restores the terminal, saves the buffer of `data` to a rescue
+/// file and writes a crash report with a backtrace to the state directory.
+pub fn install(data: SharedData) {
+    let default = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        PANICKED.store(true, Ordering::SeqCst);
+        terminal::restore();
+        // the panicking thread may hold the write lock, then `rescue_after_unwind` does it later
+        let mut files = FILES.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(d) = data.try_read_for(Duration::from_millis(50)) {
+            files.store_rescue(rescue(&d));
+        }
+        files.report = report(info, &files).ok();
+        drop(files);
+        default(info);
+    }));
+}
+
+/// Write the rescue file if the panic hook could not get to the buffer.
+/// Call after the panicking task finished unwinding and released its lock.
+pub fn rescue_after_unwind(data: &SharedData) -> CrashFiles {
+    let mut files = FILES.lock().unwrap_or_else(|e| e.into_inner());
+    if !files.rescued {
+        if let Some(d) = data.try_read_for(Duration::from_millis(500)) {
+            files.store_rescue(rescue(&d));
+        }
+        if let Some(ref report) = files.report {
+            if let Ok(mut f) = fs::OpenOptions::new().append(true).open(report) {
+                files.write_rescue(&mut f).unwrap_or(());
+            }
+        }
+    }
+    files.clone()
+}
+
+impl CrashFiles {
+    fn store_rescue(&mut self, result: io::Result<Option<PathBuf>>) {
+        match result {
+            Ok(path) => self.rescue = path,
+            Err(e) => self.rescue_error = Some(e.to_string()),
+        }
+        self.rescued = true;
+    }
+    fn write_rescue(&self, f: &mut impl Write) -> io::Result<()> {
+        match (&self.rescue, &self.rescue_error) {
+            (Some(r), _) => writeln!(f, "rescue file: {}", r.display()),
+            (None, Some(e)) => writeln!(f, "rescue file could not be written: {}", e),
+            (None, None) if self.rescued => writeln!(f, "no unsaved changes"),
+            (None, None) => writeln!(f, "buffer was locked, rescued after unwinding"),
+        }
+    }
+}
+
+fn timestamp() -> String {
+    chrono::Local::now().format("%Y%m%d-%H%M%S").to_string()
+}
+
+/// write unsaved lines to `rescue/<file name>.<time>`, returns its path
+fn rescue(data: &FileData) -> io::Result<Option<PathBuf>> {
+    if data.f_status == FileStatus::Clean {
+        return Ok(None);
+    }
+    let name = data
+        .path
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or("untitled".to_owned());
+    let path = state::state_path(format!("rescue/{}.{}", name, timestamp()))?;
+    let mut text = String::new();
+    for l in data.lines.iter() {
+        text.push_str(&l.data);
+        text.push('\n');
+    }
+    fs::write(&path, text)?;
+    Ok(Some(path))
+}
+
+fn report(info: &PanicHookInfo, files: &CrashFiles) -> io::Result<PathBuf> {
+    let path = state::state_path(format!("crash/{}.log", timestamp()))?;
+    let mut f = fs::File::create(&path)?;
+    writeln!(f, "te {} crashed at {}", env!("CARGO_PKG_VERSION"), chrono::Local::now())?;
+    writeln!(f, "{}", info)?;
+    files.write_rescue(&mut f)?;
+    writeln!(f, "\n{}", Backtrace::force_capture())?;
+    Ok(path)
+}
@@ -1,18 +1,35 @@
 use std::{
-    default,
+    collections::VecDeque,
     fs::File,
-    io::{self, stdout, BufWriter, Write},
-    iter,
+    io::{self, BufWriter, Write},
+    ops::RangeInclusive,
     path::{Path, PathBuf},
     sync::Arc,
     time::Duration,
 };
 
-use crossterm::{cursor::Show, execute};
 use parking_lot::RwLock;
-use std::time::Instant;
+use regex::Regex;
+use std::{
+    cmp::{max, min},
+    time::Instant,
+};
+use tokio::sync::Notify;
 
-use crate::cmdline::Config;
+use crate::{
+    cmdline::Config,
+    encoding::{self, Decoded, FileEncoding},
+    error::{Error, Result},
+    explorer::{Explorer, EXPLORER_WIDTH},
+    finder::Index,
+    fuzzy,
+    grep::Results,
+    language::Language,
+    macros::Macros,
+    prompt_history::PromptHistory,
+    session::Session,
+    MESSAGE_SIZE, PREFIX_SIZE, STATUS_SIZE,
+};
 
 #[derive(Debug, Clone)]
 pub struct Message {
@@ -30,20 +47,24 @@ impl Message {
     }
     pub fn with_timeout(text: String, timeout: Duration) -> Self {
         Self {
-            text: text,
-            timeout: timeout,
+            text,
+            timeout,
             time: Instant::now(),
         }
     }
-    pub fn show<'a>(&'a self) -> Option<&'a str> {
+    pub fn show(&self) -> Option<&str> {
         if self.timeout >= self.time.elapsed() {
             return Some(&self.text);
         }
-        return None;
+        None
+    }
+    /// time left until the message stops being shown, `None` if it already expired
+    pub fn remaining(&self) -> Option<Duration> {
+        self.timeout.checked_sub(self.time.elapsed())
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Line {
     pub data: String,
     pub char_len: usize,
@@ -81,8 +102,12 @@ impl Line {
     }
     /// try to remove a tab from beginning of the line returns the amount of removed characters
     pub fn back_tab(&mut self, max: usize) -> usize {
+        if self.data.starts_with('\t') {
+            self.remove(0);
+            return 1;
+        }
         let mut count = 0;
-        while self.data.chars().next() == Some(' ') && count < max {
+        while self.data.starts_with(' ') && count < max {
             self.data.remove(0);
             count += 1;
         }
@@ -105,6 +130,9 @@ impl Line {
     pub fn len(&self) -> usize {
         self.data.len()
     }
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
     pub fn get_char_pos(&self, idx: usize) -> usize {
         if idx == 0 {
             return 0;
@@ -119,6 +147,7 @@ impl Line {
                 + 1,
         )
     }
+    #[allow(unused)]
     pub fn get_char_span(&self, first: usize, last: usize) -> (usize, usize) {
         let span = last - first;
         let first = if first == 0 {
@@ -163,10 +192,202 @@ impl Line {
             },
         )
     }
+    /// length in bytes of the leading whitespace
+    pub fn indent_len(&self) -> usize {
+        self.data.len() - self.data.trim_start().len()
+    }
+    /// character column of byte offset `idx`
+    pub fn char_col(&self, idx: usize) -> usize {
+        self.data[..idx].chars().count()
+    }
+    /// screen column of byte offset `idx`, a tab reaches to the next multiple of `tab_size`
+    pub fn display_col(&self, idx: usize, tab_size: usize) -> usize {
+        self.data[..idx].chars().fold(0, |col, c| match c {
+            '\t' => (col / tab_size + 1) * tab_size,
+            _ => col + 1,
+        })
+    }
+    /// byte offset of the character shown at screen column `col`, the end of the line
+    /// when it is shorter
+    pub fn display_pos(&self, col: usize, tab_size: usize) -> usize {
+        let mut at = 0;
+        for (i, c) in self.data.char_indices() {
+            at = match c {
+                '\t' => (at / tab_size + 1) * tab_size,
+                _ => at + 1,
+            };
+            if at > col {
+                return i;
+            }
+        }
+        self.data.len()
+    }
+    /// the text as drawn, tabs turned into spaces up to the next tab stop
+    pub fn expand_tabs(&self, tab_size: usize) -> String {
+        if !self.data.contains('\t') {
+            return self.data.clone();
+        }
+        let (mut text, mut col) = (String::new(), 0);
+        for c in self.data.chars() {
+            match c {
+                '\t' => {
+                    let width = tab_size - col % tab_size;
+                    text.push_str(&" ".repeat(width));
+                    col += width;
+                }
+                c => {
+                    text.push(c);
+                    col += 1;
+                }
+            }
+        }
+        text
+    }
+    /// byte offset of the start of the word before `loc`, whitespace in between is skipped
+    pub fn prev_word_boundary(&self, loc: usize, word_chars: &str) -> usize {
+        let mut chars = self.data[..loc].char_indices().rev().peekable();
+        while chars
+            .next_if(|(_, c)| CharClass::of(*c, word_chars) == CharClass::Whitespace)
+            .is_some()
+        {}
+        let Some(&(mut start, c)) = chars.peek() else {
+            return 0;
+        };
+        let class = CharClass::of(c, word_chars);
+        for (i, c) in chars {
+            if CharClass::of(c, word_chars) != class {
+                break;
+            }
+            start = i;
+        }
+        start
+    }
+    /// byte offset of the end of the word after `loc`, whitespace in between is skipped
+    pub fn next_word_boundary(&self, loc: usize, word_chars: &str) -> usize {
+        let mut chars = self.data[loc..]
+            .char_indices()
+            .map(|(i, c)| (i + loc, c))
+            .peekable();
+        while chars
+            .next_if(|(_, c)| CharClass::of(*c, word_chars) == CharClass::Whitespace)
+            .is_some()
+        {}
+        let Some(&(_, c)) = chars.peek() else {
+            return self.data.len();
+        };
+        let class = CharClass::of(c, word_chars);
+        chars
+            .find(|(_, c)| CharClass::of(*c, word_chars) != class)
+            .map_or(self.data.len(), |(i, _)| i)
+    }
+    /// byte range of the word around `loc`, `None` if there is no word character on either side
+    pub fn word_at(&self, loc: usize, word_chars: &str) -> Option<std::ops::Range<usize>> {
+        let is_word = |c: &char| CharClass::of(*c, word_chars) == CharClass::Word;
+        let start = self.data[..loc]
+            .char_indices()
+            .rev()
+            .take_while(|(_, c)| is_word(c))
+            .last()
+            .map_or(loc, |(i, _)| i);
+        let end = self.data[loc..]
+            .char_indices()
+            .find(|(_, c)| !is_word(c))
+            .map_or(self.data.len(), |(i, _)| i + loc);
+        (start < end).then_some(start..end)
+    }
+    /// remove the bytes in `range`, both ends must be char boundaries
+    pub fn remove_range(&mut self, range: std::ops::Range<usize>) {
+        self.char_len -= self.data[range.clone()].chars().count();
+        self.data.replace_range(range, "");
+    }
 }
-#[derive(Clone, Copy, Debug)]
+
+/// What a character counts as when moving or deleting by word.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CharClass {
+    Whitespace,
+    /// letters, digits and the configured `word_chars`
+    Word,
+    Punctuation,
+}
+impl CharClass {
+    pub fn of(c: char, word_chars: &str) -> Self {
+        if c.is_whitespace() {
+            CharClass::Whitespace
+        } else if c.is_alphanumeric() || word_chars.contains(c) {
+            CharClass::Word
+        } else {
+            CharClass::Punctuation
+        }
+    }
+}
+
+/// most undo steps kept per buffer, older ones are forgotten
+const UNDO_DEPTH: usize = 100;
+/// lines searched for the partner of a bracket before giving up
+const BRACKET_SCAN: usize = 5000;
+/// brackets that are matched, opening first
+pub const BRACKETS: [(char, char); 3] = [('(', ')'), ('[', ']'), ('{', '}')];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
 pub struct TextPos(pub usize, pub usize);
 
+/// A cursor with its selection, both byte positions like `FileData::location`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Cursor {
+    pub location: TextPos,
+    pub cursor_location: TextPos,
+    pub anchor: Option<TextPos>,
+}
+
+impl Cursor {
+    /// start and end of the selection in text order, `None` when nothing is selected
+    pub fn selection(&self) -> Option<(TextPos, TextPos)> {
+        let anchor = self.anchor.filter(|a| *a != self.location)?;
+        Some((min(anchor, self.location), max(anchor, self.location)))
+    }
+}
+
+/// A rectangular selection, `anchor` and `head` are (line, column) in display columns and
+/// the columns may lie past the end of short lines.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Block {
+    pub anchor: TextPos,
+    pub head: TextPos,
+}
+impl Block {
+    pub fn rows(&self) -> RangeInclusive<usize> {
+        min(self.anchor.0, self.head.0)..=max(self.anchor.0, self.head.0)
+    }
+    pub fn cols(&self) -> std::ops::Range<usize> {
+        min(self.anchor.1, self.head.1)..max(self.anchor.1, self.head.1)
+    }
+}
+
+/// Text copied or cut inside the editor.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Clipboard {
+    Text(String),
+    /// one string per line of a rectangular selection
+    Block(Vec<String>),
+}
+
+/// What undo brings back: the text with the cursor and the selection at that time.
+#[derive(Clone, Debug)]
+pub struct Snapshot {
+    pub lines: Vec<Line>,
+    pub location: TextPos,
+    pub anchor: Option<TextPos>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct History {
+    undo: VecDeque<Snapshot>,
+    redo: Vec<Snapshot>,
+    /// the last step was typing, more typed characters join it instead of starting a new one
+    pub typing: bool,
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
 pub enum FileStatus {
     /// file was not edited
@@ -180,6 +401,45 @@ pub enum PromptType {
     #[default]
     Save,
     Search,
+    /// re-open the file with the given encoding
+    Encoding,
+    /// name of the register to record a macro into
+    RecordMacro,
+    /// register and number of times to play it
+    PlayMacro,
+    /// pick a command from the palette
+    Command,
+    /// a command line command with its arguments
+    CommandLine,
+    /// path of a file to open in place of the buffer
+    Open,
+    /// y or n for the action of `Prompt::pending`
+    Confirm,
+    /// path of a file to create from the explorer, a trailing `/` makes a folder
+    NewFile,
+    /// new name of the entry selected in the explorer
+    Rename,
+    /// fuzzy pattern picking a file of the project
+    Finder,
+    /// regex to search the files of the project for
+    Grep,
+}
+impl PromptType {
+    /// name of the history the prompt keeps, `None` for prompts without one
+    pub fn history_key(&self) -> Option<&'static str> {
+        match self {
+            PromptType::Save | PromptType::Open | PromptType::NewFile | PromptType::Rename => {
+                Some("path")
+            }
+            PromptType::Search => Some("search"),
+            PromptType::Finder => Some("find"),
+            PromptType::Grep => Some("grep"),
+            PromptType::Encoding => Some("encoding"),
+            PromptType::RecordMacro | PromptType::PlayMacro => Some("macro"),
+            PromptType::CommandLine => Some("command"),
+            PromptType::Command | PromptType::Confirm => None,
+        }
+    }
 }
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
 pub enum PromptStatus {
@@ -197,20 +457,154 @@ pub struct Prompt {
     pub cursor: usize,
     pub status: PromptStatus,
     pub left_visible: usize,
+    /// entries to pick from, filtered by the typed text
+    pub list: Option<List>,
+    /// earlier entries, oldest first, recalled with Up and Down
+    pub history: Vec<String>,
+    /// the history entry shown and the text typed before Up was first pressed
+    pub recalled: Option<(usize, String)>,
+    /// the text was filled in when the prompt opened, typing replaces it
+    pub prefilled: bool,
+    /// directory relative paths typed into the prompt start in, empty for the working one
+    pub dir: PathBuf,
+    /// what a confirmation prompt does on yes
+    pub pending: Option<Pending>,
+    /// files still being found for the list
+    pub index: Option<Index>,
+    /// the file of the selected entry and its first lines
+    pub preview: Option<(PathBuf, Vec<String>)>,
+}
+
+/// An action waiting for an answer in a `PromptType::Confirm` prompt.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Pending {
+    /// save to the path, creating missing directories
+    Save(PathBuf),
+    /// move the first path to the second one, replacing it
+    Rename(PathBuf, PathBuf),
+    /// remove the file or the folder with everything in it
+    Delete(PathBuf),
 }
 impl Prompt {
     pub fn new(message: &str, p_type: PromptType) -> Prompt {
         Prompt {
             message: Line::new(message),
             data: Line::new(""),
-            p_type: p_type,
+            p_type,
             location: 0,
             cursor: 0,
             status: PromptStatus::Pending,
             left_visible: 0,
+            list: None,
+            history: Vec::new(),
+            recalled: None,
+            prefilled: false,
+            dir: PathBuf::new(),
+            pending: None,
+            index: None,
+            preview: None,
+        }
+    }
+    /// move the cursor to byte `location` of the typed text
+    pub fn move_to(&mut self, location: usize) {
+        self.location = location;
+        self.cursor = self.data.char_col(location);
+    }
+    /// remove `range` of the typed text, the cursor goes to where it started
+    pub fn remove_range(&mut self, range: std::ops::Range<usize>) {
+        let start = range.start;
+        self.data.remove_range(range);
+        self.move_to(start);
+    }
+    /// replace the typed text, the cursor goes to its end
+    pub fn set_text(&mut self, text: &str) {
+        self.data = Line::new(text);
+        self.location = self.data.len();
+        self.cursor = self.data.char_len;
+        self.left_visible = 0;
+    }
+    /// show the history entry before (`back`) or after the one shown, past the newest one
+    /// the text typed before comes back
+    pub fn recall(&mut self, back: bool) {
+        let (pos, draft) = match self.recalled.take() {
+            Some(r) => r,
+            None => (self.history.len(), self.data.data.clone()),
+        };
+        let pos = if back {
+            pos.saturating_sub(1)
+        } else {
+            min(pos + 1, self.history.len())
+        };
+        match self.history.get(pos) {
+            Some(entry) => {
+                let entry = entry.clone();
+                self.set_text(&entry);
+                self.recalled = Some((pos, draft));
+            }
+            None => self.set_text(&draft),
+        }
+    }
+    pub fn with_list(message: &str, p_type: PromptType, items: Vec<ListItem>) -> Prompt {
+        Prompt {
+            list: Some(List::new(items)),
+            ..Prompt::new(message, p_type)
         }
     }
 }
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ListItem {
+    pub label: String,
+    /// shown right aligned, like the key bound to a command
+    pub detail: String,
+}
+
+/// Entries shown over the text above a prompt, narrowed down by fuzzy matching what is
+/// typed into it and picked with Up, Down and Enter.
+#[derive(Clone, Debug, Default)]
+pub struct List {
+    pub items: Vec<ListItem>,
+    /// the matching items, best first, with the char columns of the label that matched
+    pub shown: Vec<(usize, Vec<usize>)>,
+    /// index into `shown`
+    pub selected: usize,
+}
+impl List {
+    pub fn new(items: Vec<ListItem>) -> Self {
+        let mut list = Self {
+            items,
+            ..Self::default()
+        };
+        list.filter("");
+        list
+    }
+    /// show the items matching `pattern` and select the best one
+    pub fn filter(&mut self, pattern: &str) {
+        let mut shown: Vec<_> = self
+            .items
+            .iter()
+            .enumerate()
+            .filter_map(|(i, item)| {
+                let (score, cols) = fuzzy::score(pattern, &item.label)?;
+                Some((score, i, cols))
+            })
+            .collect();
+        shown.sort_by_key(|(score, i, _)| (std::cmp::Reverse(*score), *i));
+        self.shown = shown.into_iter().map(|(_, i, cols)| (i, cols)).collect();
+        self.selected = 0;
+    }
+    /// move the selection by `by` entries, wrapping around at either end
+    pub fn select(&mut self, by: isize) {
+        if !self.shown.is_empty() {
+            let len = self.shown.len() as isize;
+            self.selected = (self.selected as isize + by).rem_euclid(len) as usize;
+        }
+    }
+    /// index into `items` of the selected entry
+    pub fn selected_item(&self) -> Option<usize> {
+        self.shown.get(self.selected).map(|(i, _)| *i)
+    }
+}
 #[derive(Clone, Debug)]
 pub struct FileData {
     pub lines: Vec<Line>,
@@ -218,74 +612,719 @@ pub struct FileData {
     pub location: TextPos,
     pub ended: bool,
     pub size: TextPos,
+    /// columns to the next tab stop, from `Config::tab_size`
+    pub tab_size: usize,
     pub top_visible: usize,
     pub left_visible: usize,
     pub cursor_location: TextPos,
     pub message: Message,
     pub f_status: FileStatus,
     pub prompt: Option<Prompt>,
-    pub redraw: bool,
-}
-impl Drop for FileData {
-    fn drop(&mut self) {
-        execute!(stdout(), Show).unwrap_or(());
-    }
+    /// set after the first Ctrl+Q on an edited file, the next Ctrl+Q quits
+    pub quit_pending: bool,
+    pub encoding: FileEncoding,
+    /// binary files are shown but can't be edited or saved
+    pub read_only: bool,
+    /// byte position of the other end of the selection, the cursor is at `location`
+    pub anchor: Option<TextPos>,
+    /// cursors besides the main one, edits run once per cursor with each swapped into
+    /// `location`, `cursor_location` and `anchor`
+    pub cursors: Vec<Cursor>,
+    /// rectangular selection, the cursor follows its head
+    pub block: Option<Block>,
+    pub clipboard: Option<Clipboard>,
+    pub macros: Macros,
+    pub prompt_history: PromptHistory,
+    pub session: Session,
+    pub history: History,
+    /// the other open buffers, the next one first
+    pub buffers: Vec<FileData>,
+    pub explorer: Option<Explorer>,
+    /// the buffer lists project grep results
+    pub results: Option<Results>,
 }
 impl FileData {
-    pub fn new() -> Self {
-        let (w, h) = crossterm::terminal::size().unwrap();
+    /// an empty buffer for a screen of `size` (rows, columns)
+    pub fn new(size: TextPos) -> Self {
         Self {
             lines: vec![Line::new("")],
             path: PathBuf::default(),
             cursor_location: TextPos(0, 0),
             location: TextPos(0, 0),
             ended: false,
-            size: TextPos(h.into(), w.into()),
+            size,
+            tab_size: 4,
             top_visible: 0,
             left_visible: 0,
-            message: Message::new("Press Ctrl+Q to quit".to_owned()),
+            message: Message::new("Ctrl+Q: quit, F1: commands".to_owned()),
             f_status: FileStatus::Clean,
             prompt: None,
-            redraw: true,
-        }
-    }
-    pub fn from_path(path: &Path, config: Config) -> Self {
-        let (w, h) = crossterm::terminal::size().unwrap();
-        let tab: String = iter::repeat(' ').take(config.tab_size).collect();
-        let mut lines: Vec<Line> =
-            String::from_utf8(std::fs::read(path).unwrap_or("".bytes().collect()))
-                .unwrap_or("".to_string())
-                .replace('\t', &tab)
-                .lines()
-                .map(|s| Line::from(s))
-                .collect();
-
-        if lines.is_empty() {
-            lines = vec!["".into()]
+            quit_pending: false,
+            encoding: FileEncoding::default(),
+            read_only: false,
+            anchor: None,
+            cursors: Vec::new(),
+            block: None,
+            clipboard: None,
+            macros: Macros::default(),
+            prompt_history: PromptHistory::default(),
+            session: Session::default(),
+            history: History::default(),
+            buffers: Vec::new(),
+            explorer: None,
+            results: None,
         }
-
-        Self {
-            lines,
-            path: PathBuf::from(path),
-            cursor_location: TextPos(0, 0),
-            location: TextPos(0, 0),
-            ended: false,
-            size: TextPos(h.into(), w.into()),
-            top_visible: 0,
-            left_visible: 0,
-            message: Message::new("Press Ctrl+Q to quit".to_owned()),
-            f_status: FileStatus::Clean,
-            prompt: None,
-            redraw: true,
+    }
+    /// a buffer holding `text`, not backed by a file
+    pub fn from_text(text: &str, config: &Config, size: TextPos) -> Self {
+        let mut data = Self::new(size);
+        data.tab_size = config.tab_size.max(1);
+        data.set_text(text, FileEncoding::default(), config);
+        data
+    }
+    /// open `path`, a file that doesn't exist yet gives an empty buffer
+    pub fn from_path(path: &Path, config: Config, size: TextPos) -> Result<Self> {
+        let mut data = Self::new(size);
+        data.path = PathBuf::from(path);
+        data.tab_size = config.tab_size.max(1);
+        let bytes = match std::fs::read(path) {
+            Ok(b) => b,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                data.message = Message::new(format!("New file: {}", path.display()));
+                return Ok(data);
+            }
+            Err(source) => {
+                return Err(Error::Open {
+                    path: data.path.to_owned(),
+                    source,
+                })
+            }
+        };
+        match encoding::detect(&bytes, config.legacy_encoding) {
+            Decoded::Text(text, enc) => data.set_text(&text, enc, &config),
+            Decoded::Binary(text, enc) => {
+                data.set_text(&text, enc, &config);
+                data.read_only = true;
+                data.message = Message::new("Binary file, opened read-only".to_owned());
+            }
+        }
+        Ok(data)
+    }
+    /// read the file again decoding it with `enc`, a binary file stays read-only
+    pub fn reopen(&mut self, enc: &'static encoding_rs::Encoding, config: &Config) -> Result<()> {
+        let bytes = std::fs::read(&self.path).map_err(|source| Error::Open {
+            path: self.path.to_owned(),
+            source,
+        })?;
+        let (text, enc) = encoding::decode_as(&bytes, enc);
+        self.set_text(&text, enc, config);
+        self.read_only = matches!(encoding::detect(&bytes, enc.encoding), Decoded::Binary(..));
+        self.anchor = None;
+        self.cursors.clear();
+        self.block = None;
+        self.history = History::default();
+        self.location = TextPos(0, 0);
+        self.cursor_location = TextPos(0, 0);
+        self.top_visible = 0;
+        self.left_visible = 0;
+        self.f_status = FileStatus::Clean;
+        Ok(())
+    }
+    fn set_text(&mut self, text: &str, enc: FileEncoding, config: &Config) {
+        let lines = if config.use_tabs {
+            text.lines().map(Line::from).collect()
+        } else {
+            let tab = " ".repeat(config.tab_size);
+            text.replace('\t', &tab).lines().map(Line::from).collect()
+        };
+        self.lines = lines;
+        if self.lines.is_empty() {
+            self.lines = vec!["".into()]
         }
+        self.encoding = enc;
     }
-    pub fn save(&self) -> io::Result<()> {
-        let mut w = BufWriter::new(File::create(self.path.to_owned())?);
+    /// write the buffer in its encoding
+    pub fn save(&self) -> Result<()> {
+        if self.read_only {
+            return Err(Error::ReadOnly(self.path.to_owned()));
+        }
+        let mut text = String::new();
         for l in self.lines.iter() {
-            write!(w, "{}\r\n", l.data)?;
+            text.push_str(&l.data);
+            text.push_str("\r\n");
         }
-        w.flush()?;
-        Ok(())
+        let bytes = encoding::encode(&text, self.encoding).map_err(|c| Error::Unencodable {
+            path: self.path.to_owned(),
+            c,
+            encoding: self.encoding.encoding.name(),
+        })?;
+        let write = || -> io::Result<()> {
+            let mut w = BufWriter::new(File::create(&self.path)?);
+            w.write_all(&bytes)?;
+            w.flush()
+        };
+        write().map_err(|source| Error::Save {
+            path: self.path.to_owned(),
+            source,
+        })
+    }
+    /// rows of text on the screen, without the status and message lines
+    pub fn text_height(&self) -> usize {
+        self.size.0.saturating_sub(STATUS_SIZE + MESSAGE_SIZE)
+    }
+    /// screen column the text starts at, right of the explorer and the line numbers
+    pub fn text_left(&self) -> usize {
+        let pane = match self.explorer {
+            Some(ref e) if e.visible => EXPLORER_WIDTH,
+            _ => 0,
+        };
+        pane + PREFIX_SIZE
+    }
+    /// columns of text on the screen
+    pub fn text_width(&self) -> usize {
+        self.size.1.saturating_sub(self.text_left()).max(1)
+    }
+    /// a task is still finding files for the finder or matches for the results buffer
+    pub fn busy(&self) -> bool {
+        let indexing = self
+            .prompt
+            .as_ref()
+            .and_then(|p| p.index.as_ref())
+            .is_some_and(|i| !i.done());
+        indexing || self.results.as_ref().is_some_and(|r| !r.search.done())
+    }
+    /// move over what belongs to the editor rather than to one file from `other`: the
+    /// clipboard, macros, prompt history, the session, the other buffers and the explorer
+    pub fn take_session(&mut self, other: &mut FileData) {
+        self.size = other.size;
+        self.clipboard = other.clipboard.take();
+        self.macros = std::mem::take(&mut other.macros);
+        self.prompt_history = std::mem::take(&mut other.prompt_history);
+        self.session = std::mem::take(&mut other.session);
+        self.buffers = std::mem::take(&mut other.buffers);
+        self.explorer = other.explorer.take();
+    }
+    /// put the cursor on line `pos.0` at byte offset `pos.1`, keeping the character column in sync
+    pub fn move_to(&mut self, pos: TextPos) {
+        self.location = pos;
+        self.cursor_location = TextPos(pos.0, self.lines[pos.0].char_col(pos.1));
+    }
+    /// screen column of the main cursor within the text
+    pub fn display_col(&self) -> usize {
+        self.lines[self.location.0].display_col(self.location.1, self.tab_size)
+    }
+    /// byte offset on `row` below or above the main cursor on the screen
+    pub fn same_column(&self, row: usize) -> usize {
+        self.lines[row].display_pos(self.display_col(), self.tab_size)
+    }
+    /// the main cursor
+    pub fn cursor(&self) -> Cursor {
+        Cursor {
+            location: self.location,
+            cursor_location: self.cursor_location,
+            anchor: self.anchor,
+        }
+    }
+    pub fn set_cursor(&mut self, c: Cursor) {
+        self.location = c.location;
+        self.cursor_location = c.cursor_location;
+        self.anchor = c.anchor;
+    }
+    /// make `c` the main cursor and keep the current one as an extra cursor
+    pub fn add_cursor(&mut self, c: Cursor) {
+        self.cursors.push(self.cursor());
+        self.set_cursor(c);
+        self.merge_cursors();
+    }
+    /// a cursor at byte position `pos` without a selection
+    pub fn cursor_at(&self, pos: TextPos) -> Cursor {
+        Cursor {
+            location: pos,
+            cursor_location: TextPos(pos.0, self.lines[pos.0].char_col(pos.1)),
+            anchor: None,
+        }
+    }
+    /// drop extra cursors that ended up on the same place as another one
+    pub fn merge_cursors(&mut self) {
+        let main = self.location;
+        let mut seen = vec![main];
+        self.cursors.retain(|c| {
+            let new = !seen.contains(&c.location);
+            seen.push(c.location);
+            new
+        });
+    }
+    /// position counted from the end of the buffer as (lines below, bytes to the end of the
+    /// line), it stays valid while text before it changes
+    pub fn to_tail(&self, p: TextPos) -> TextPos {
+        TextPos(self.lines.len() - 1 - p.0, self.lines[p.0].len() - p.1)
+    }
+    pub fn from_tail(&self, t: TextPos) -> TextPos {
+        let row = (self.lines.len() - 1).saturating_sub(t.0);
+        let line = &self.lines[row].data;
+        TextPos(row, line.floor_char_boundary(line.len().saturating_sub(t.1)))
+    }
+    /// start and end of the selection in text order, `None` when nothing is selected
+    pub fn selection(&self) -> Option<(TextPos, TextPos)> {
+        self.cursor().selection()
+    }
+    /// lines touched by a selection over several lines, a last line selected up to
+    /// column 0 does not count
+    pub fn selected_rows(&self) -> Option<RangeInclusive<usize>> {
+        let (start, end) = self.selection().filter(|(s, e)| s.0 != e.0)?;
+        let last = if end.1 == 0 { end.0 - 1 } else { end.0 };
+        Some(start.0..=last)
+    }
+    /// remove the selected text and put the cursor where it was, false if nothing is selected
+    pub fn delete_selection(&mut self) -> bool {
+        let Some((start, end)) = self.selection() else {
+            self.anchor = None;
+            return false;
+        };
+        let tail = self.lines[end.0].data[end.1..].to_owned();
+        self.lines.drain(start.0 + 1..=end.0);
+        let line = &mut self.lines[start.0];
+        line.remove_range(start.1..line.len());
+        line.push_str(&tail);
+        self.anchor = None;
+        self.move_to(start);
+        self.f_status = FileStatus::Edited;
+        true
+    }
+    /// the bracket at the cursor, or else right before it, with the bracket it pairs with
+    pub fn bracket_pair(&self) -> Option<(TextPos, TextPos)> {
+        let pos = self.location;
+        let (prev, _) = self.get_next_and_prev_chars();
+        if let Some(m) = self.matching_bracket(pos) {
+            return Some((pos, m));
+        }
+        if pos.1 == 0 {
+            return None;
+        }
+        let before = TextPos(pos.0, prev);
+        self.matching_bracket(before).map(|m| (before, m))
+    }
+    /// position of the bracket that pairs with the one at `pos`, nested pairs are skipped
+    pub fn matching_bracket(&self, pos: TextPos) -> Option<TextPos> {
+        let c = self.lines[pos.0].data[pos.1..].chars().next()?;
+        let mut depth = 0;
+        if let Some(&(open, close)) = BRACKETS.iter().find(|b| b.0 == c) {
+            for (row, line) in self.lines.iter().enumerate().skip(pos.0).take(BRACKET_SCAN) {
+                let start = if row == pos.0 { pos.1 } else { 0 };
+                for (i, ch) in line.data[start..].char_indices() {
+                    if ch == open {
+                        depth += 1;
+                    } else if ch == close {
+                        depth -= 1;
+                        if depth == 0 {
+                            return Some(TextPos(row, start + i));
+                        }
+                    }
+                }
+            }
+        } else if let Some(&(open, close)) = BRACKETS.iter().find(|b| b.1 == c) {
+            let first = pos.0.saturating_sub(BRACKET_SCAN);
+            for (row, line) in self.lines[first..=pos.0].iter().enumerate().rev() {
+                let row = row + first;
+                let end = if row == pos.0 { pos.1 + c.len_utf8() } else { line.len() };
+                for (i, ch) in line.data[..end].char_indices().rev() {
+                    if ch == close {
+                        depth += 1;
+                    } else if ch == open {
+                        depth -= 1;
+                        if depth == 0 {
+                            return Some(TextPos(row, i));
+                        }
+                    }
+                }
+            }
+        }
+        None
+    }
+    /// rows a line operation works on: the selected lines or the cursor line
+    pub fn target_rows(&self) -> RangeInclusive<usize> {
+        self.selected_rows()
+            .unwrap_or(self.location.0..=self.location.0)
+    }
+    /// replace the matches of `pattern` on `rows`, only the first one on each line unless
+    /// `global`, returns how many were replaced
+    pub fn substitute(
+        &mut self,
+        rows: RangeInclusive<usize>,
+        pattern: &Regex,
+        replacement: &str,
+        global: bool,
+    ) -> usize {
+        let mut count = 0;
+        for row in rows {
+            let line = &self.lines[row].data;
+            let found = pattern.find_iter(line).count();
+            if found == 0 {
+                continue;
+            }
+            let limit = if global { 0 } else { 1 };
+            let new = pattern.replacen(line, limit, replacement).into_owned();
+            count += if global { found } else { 1 };
+            self.lines[row] = Line::from(new);
+        }
+        if count > 0 {
+            self.clamp_cursor();
+            self.f_status = FileStatus::Edited;
+        }
+        count
+    }
+    /// put `rows` in byte order
+    pub fn sort_lines(&mut self, rows: RangeInclusive<usize>) {
+        self.lines[rows].sort_by(|a, b| a.data.cmp(&b.data));
+        self.clamp_cursor();
+        self.f_status = FileStatus::Edited;
+    }
+    /// put `lines` in place of `rows`
+    pub fn replace_lines(&mut self, rows: RangeInclusive<usize>, lines: Vec<Line>) {
+        let lines = if lines.is_empty() {
+            vec![Line::from("")]
+        } else {
+            lines
+        };
+        self.lines.splice(rows, lines);
+        self.clamp_cursor();
+        self.f_status = FileStatus::Edited;
+    }
+    /// keep the cursor on its row and column after the text under it changed,
+    /// the selection and the other cursors are dropped
+    fn clamp_cursor(&mut self) {
+        self.anchor = None;
+        self.cursors.clear();
+        let row = min(self.location.0, self.lines.len() - 1);
+        let col = self.lines[row].get_char_pos(self.cursor_location.1);
+        self.move_to(TextPos(row, col));
+    }
+    /// move the cursor and the selection `by` lines down, negative moves up
+    fn shift_rows(&mut self, by: isize) {
+        self.location.0 = self.location.0.saturating_add_signed(by);
+        self.cursor_location.0 = self.location.0;
+        if let Some(ref mut a) = self.anchor {
+            a.0 = a.0.saturating_add_signed(by);
+        }
+    }
+    /// insert a copy of `rows` below them, the cursor and the selection go to the copy
+    pub fn duplicate_lines(&mut self, rows: RangeInclusive<usize>) {
+        let copy = self.lines[rows.clone()].to_vec();
+        let after = rows.end() + 1;
+        self.lines.splice(after..after, copy);
+        self.shift_rows(rows.count() as isize);
+        self.f_status = FileStatus::Edited;
+    }
+    /// remove `rows`, the cursor stays in its column on the line that moved up
+    pub fn delete_lines(&mut self, rows: RangeInclusive<usize>) {
+        let first = *rows.start();
+        let col = self.display_col();
+        self.lines.drain(rows);
+        if self.lines.is_empty() {
+            self.lines.push(Line::from(""));
+        }
+        let row = first.min(self.lines.len() - 1);
+        let col = self.lines[row].display_pos(col, self.tab_size);
+        self.anchor = None;
+        self.move_to(TextPos(row, col));
+        self.f_status = FileStatus::Edited;
+    }
+    /// swap `rows` with the line above or below them, false at the edge of the buffer
+    pub fn move_lines(&mut self, rows: RangeInclusive<usize>, up: bool) -> bool {
+        let (first, last) = (*rows.start(), *rows.end());
+        if up && first > 0 {
+            let line = self.lines.remove(first - 1);
+            self.lines.insert(last, line);
+            self.shift_rows(-1);
+        } else if !up && last + 1 < self.lines.len() {
+            let line = self.lines.remove(last + 1);
+            self.lines.insert(first, line);
+            self.shift_rows(1);
+        } else {
+            return false;
+        }
+        self.f_status = FileStatus::Edited;
+        true
+    }
+    /// join the line after `rows` into them, or all of `rows` if they are several, with the
+    /// whitespace around each join replaced by one space
+    pub fn join_lines(&mut self, rows: RangeInclusive<usize>) {
+        let first = *rows.start();
+        let last = if rows.end() > rows.start() {
+            *rows.end()
+        } else {
+            first + 1
+        };
+        if last >= self.lines.len() {
+            return;
+        }
+        let mut joined = self.lines[first].data.to_owned();
+        let mut join_at = 0;
+        for line in self.lines.drain(first + 1..=last) {
+            let next = line.data.trim_start();
+            joined.truncate(joined.trim_end().len());
+            join_at = joined.len();
+            if !joined.trim_start().is_empty() && !next.is_empty() {
+                joined.push(' ');
+            }
+            joined.push_str(next);
+        }
+        self.lines[first] = Line::from(joined);
+        self.anchor = None;
+        self.move_to(TextPos(first, join_at));
+        self.f_status = FileStatus::Edited;
+    }
+    /// comment out the non-blank lines in `rows`, or uncomment them if all of them are
+    /// comments, the markers line up at the shallowest indentation. Languages without line
+    /// comments get every line wrapped in a block comment. False if there is no comment syntax.
+    pub fn toggle_comment(&mut self, rows: RangeInclusive<usize>, lang: &Language) -> bool {
+        let (open, close) = match (lang.line_comment, lang.block_comment) {
+            (Some(prefix), _) => (prefix, ""),
+            (None, Some(block)) => block,
+            (None, None) => return false,
+        };
+        let filled: Vec<usize> = rows
+            .filter(|r| !self.lines[*r].data.trim().is_empty())
+            .collect();
+        let commented = filled.iter().all(|r| {
+            let t = self.lines[*r].data.trim();
+            t.len() >= open.len() + close.len() && t.starts_with(open) && t.ends_with(close)
+        });
+        let indent = filled
+            .iter()
+            .map(|r| self.lines[*r].indent_len())
+            .min()
+            .unwrap_or(0);
+        let mut location = self.location;
+        let mut anchor = self.anchor;
+        for row in filled {
+            let line = &mut self.lines[row];
+            // positions after `at` move by `added` bytes, or back by `removed` bytes
+            let (at, added, removed) = if commented {
+                let at = line.indent_len();
+                let mut len = open.len();
+                if line.data[at + len..].starts_with(' ') {
+                    len += 1;
+                }
+                line.remove_range(at..at + len);
+                if !close.is_empty() {
+                    let end = line.data.trim_end().len();
+                    let mut start = end - close.len();
+                    if line.data[..start].ends_with(' ') {
+                        start -= 1;
+                    }
+                    line.remove_range(start..line.len());
+                }
+                (at, 0, len)
+            } else {
+                line.insert_str(indent, &format!("{} ", open));
+                if !close.is_empty() {
+                    line.push_str(&format!(" {}", close));
+                }
+                (indent, open.len() + 1, 0)
+            };
+            for p in std::iter::once(&mut location).chain(anchor.as_mut()) {
+                if p.0 == row && p.1 >= at {
+                    p.1 = (p.1 + added).saturating_sub(removed).max(at).min(line.len());
+                }
+            }
+            self.f_status = FileStatus::Edited;
+        }
+        self.anchor = anchor;
+        self.move_to(location);
+        true
+    }
+    /// the selected text with `\n` between lines
+    pub fn selected_text(&self) -> Option<String> {
+        let (start, end) = self.selection()?;
+        if start.0 == end.0 {
+            return Some(self.lines[start.0].data[start.1..end.1].to_owned());
+        }
+        let mut text = self.lines[start.0].data[start.1..].to_owned();
+        for l in &self.lines[start.0 + 1..end.0] {
+            text.push('\n');
+            text.push_str(&l.data);
+        }
+        text.push('\n');
+        text.push_str(&self.lines[end.0].data[..end.1]);
+        Some(text)
+    }
+    /// insert `text` at the cursor, a `\n` starts a new line, the cursor ends up after it
+    pub fn insert_text(&mut self, text: &str) {
+        let pos = self.location;
+        let tail = self.lines[pos.0].split_at(pos.1);
+        let mut row = pos.0;
+        let mut pieces = text.split('\n');
+        self.lines[row].push_str(pieces.next().unwrap_or(""));
+        for piece in pieces {
+            row += 1;
+            self.lines.insert(row, Line::from(piece));
+        }
+        let col = self.lines[row].len();
+        self.lines[row].push_str(&tail.data);
+        self.move_to(TextPos(row, col));
+        self.f_status = FileStatus::Edited;
+    }
+    /// the columns of the block on each of its lines, short lines give what they have
+    pub fn block_text(&self, b: Block) -> Vec<String> {
+        let cols = b.cols();
+        b.rows()
+            .map(|r| {
+                let l = &self.lines[r];
+                l.data[l.get_char_pos(cols.start)..l.get_char_pos(cols.end)].to_owned()
+            })
+            .collect()
+    }
+    /// remove the columns of the block from its lines, the block shrinks to its left edge
+    pub fn delete_block(&mut self) {
+        let Some(b) = self.block else {
+            return;
+        };
+        let cols = b.cols();
+        for r in b.rows() {
+            let l = &mut self.lines[r];
+            let range = l.get_char_pos(cols.start)..l.get_char_pos(cols.end);
+            if !range.is_empty() {
+                l.remove_range(range);
+                self.f_status = FileStatus::Edited;
+            }
+        }
+        self.collapse_block(cols.start);
+    }
+    /// replace the columns of the block with `text` on each of its lines, short lines are
+    /// padded with spaces up to the block
+    pub fn type_in_block(&mut self, text: &str) {
+        self.delete_block();
+        let Some(b) = self.block else {
+            return;
+        };
+        let col = b.cols().start;
+        for r in b.rows() {
+            self.pad_to(r, col);
+            let at = self.lines[r].get_char_pos(col);
+            self.lines[r].insert_str(at, text);
+        }
+        self.f_status = FileStatus::Edited;
+        self.collapse_block(col + text.chars().count());
+    }
+    /// Backspace or Delete on the block: remove its columns, or with no width the character
+    /// before or after its column on every line long enough to have one
+    pub fn erase_block(&mut self, back: bool) {
+        let Some(b) = self.block else {
+            return;
+        };
+        let col = b.cols().start;
+        if !b.cols().is_empty() {
+            self.delete_block();
+            return;
+        }
+        if back && col == 0 {
+            return;
+        }
+        let at = if back { col - 1 } else { col };
+        for r in b.rows() {
+            let l = &mut self.lines[r];
+            if l.char_len > at {
+                let range = l.get_char_pos(at)..l.get_char_pos(at + 1);
+                l.remove_range(range);
+                self.f_status = FileStatus::Edited;
+            }
+        }
+        self.collapse_block(at);
+    }
+    /// paste `lines` as a rectangle with its top left corner at the block or the cursor,
+    /// lines are added at the end of the buffer when needed
+    pub fn paste_block(&mut self, lines: &[String]) {
+        let (top, col) = match self.block {
+            Some(b) => {
+                self.delete_block();
+                (*b.rows().start(), b.cols().start)
+            }
+            None => (self.location.0, self.cursor_location.1),
+        };
+        let mut end = TextPos(top, 0);
+        for (i, text) in lines.iter().enumerate() {
+            let row = top + i;
+            if row == self.lines.len() {
+                self.lines.push(Line::from(""));
+            }
+            self.pad_to(row, col);
+            let at = self.lines[row].get_char_pos(col);
+            self.lines[row].insert_str(at, text);
+            end = TextPos(row, at + text.len());
+        }
+        self.block = None;
+        self.anchor = None;
+        self.move_to(end);
+        self.f_status = FileStatus::Edited;
+    }
+    /// make the block zero wide at column `col`, the cursor goes to its head
+    fn collapse_block(&mut self, col: usize) {
+        if let Some(ref mut b) = self.block {
+            b.anchor.1 = col;
+            b.head.1 = col;
+            let row = b.head.0;
+            self.move_to(TextPos(row, self.lines[row].get_char_pos(col)));
+        }
+    }
+    /// add spaces to line `row` until it is at least `col` characters long
+    fn pad_to(&mut self, row: usize, col: usize) {
+        let l = &mut self.lines[row];
+        if l.char_len < col {
+            l.push_str(&" ".repeat(col - l.char_len));
+        }
+    }
+    /// remember the current text as an undo step, the redo steps are dropped
+    pub fn checkpoint(&mut self) {
+        let snapshot = self.snapshot();
+        self.history.undo.push_back(snapshot);
+        if self.history.undo.len() > UNDO_DEPTH {
+            self.history.undo.pop_front();
+        }
+        self.history.redo.clear();
+    }
+    /// forget the last checkpoint if the text did not change since
+    pub fn drop_unchanged_checkpoint(&mut self) {
+        if self
+            .history
+            .undo
+            .back()
+            .is_some_and(|s| s.lines == self.lines)
+        {
+            self.history.undo.pop_back();
+        }
+    }
+    pub fn undo(&mut self) -> bool {
+        let Some(s) = self.history.undo.pop_back() else {
+            return false;
+        };
+        self.history.redo.push(self.snapshot());
+        self.restore(s);
+        true
+    }
+    pub fn redo(&mut self) -> bool {
+        let Some(s) = self.history.redo.pop() else {
+            return false;
+        };
+        self.history.undo.push_back(self.snapshot());
+        self.restore(s);
+        true
+    }
+    fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            lines: self.lines.clone(),
+            location: self.location,
+            anchor: self.anchor,
+        }
+    }
+    fn restore(&mut self, s: Snapshot) {
+        self.lines = s.lines;
+        self.anchor = s.anchor;
+        self.cursors.clear();
+        self.block = None;
+        self.move_to(s.location);
+        self.history.typing = false;
+        self.f_status = FileStatus::Edited;
     }
     #[allow(unused)]
     pub fn get_next_and_prev_chars(&self) -> (usize, usize) {
@@ -296,32 +1335,42 @@ impl FileData {
 #[derive(Clone, Debug)]
 pub struct SharedData {
     data: Arc<RwLock<FileData>>,
+    redraw: Arc<Notify>,
 }
 
 impl Drop for SharedData {
     fn drop(&mut self) {
-        eprintln!("Dropped file data");
         self.data.write().ended = true;
-        if std::thread::panicking() {
-            eprintln!("panicking");
-        }
+        self.redraw.notify_one();
     }
 }
 
 impl SharedData {
-    pub fn new() -> Self {
+    pub fn new(size: TextPos) -> Self {
         Self {
-            data: Arc::new(RwLock::new(FileData::new())),
+            data: Arc::new(RwLock::new(FileData::new(size))),
+            redraw: Arc::new(Notify::new()),
         }
     }
-    pub fn from_path(path: &Path, config: Config) -> Self {
-        Self {
-            data: Arc::new(RwLock::new(FileData::from_path(path, config))),
-        }
+    pub fn from_path(path: &Path, config: Config, size: TextPos) -> Result<Self> {
+        Ok(Self {
+            data: Arc::new(RwLock::new(FileData::from_path(path, config, size)?)),
+            redraw: Arc::new(Notify::new()),
+        })
     }
-    pub fn save(&self) -> io::Result<()> {
+    /// wake the renderer, a request made while it is drawing is kept for the next wait
+    pub fn request_redraw(&self) {
+        self.redraw.notify_one();
+    }
+    /// resolves after the next call to `request_redraw`
+    pub async fn redraw_requested(&self) {
+        self.redraw.notified().await
+    }
+    #[allow(unused)]
+    pub fn save(&self) -> Result<()> {
         self.data.read().save()
     }
+    #[allow(unused)]
     pub fn get_next_and_prev_chars(&self) -> (usize, usize) {
         let wdata = self.data.write();
         let line_text = &wdata.lines[wdata.location.0].data;
@@ -348,4 +1397,10 @@ impl SharedData {
     ) -> parking_lot::lock_api::RwLockReadGuard<'_, parking_lot::RawRwLock, FileData> {
         self.data.read()
     }
+    pub fn try_read_for(
+        &self,
+        timeout: Duration,
+    ) -> Option<parking_lot::lock_api::RwLockReadGuard<'_, parking_lot::RawRwLock, FileData>> {
+        self.data.try_read_for(timeout)
+    }
 }
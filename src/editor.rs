@@ -0,0 +1,1954 @@
+use std::{
+    cmp::min,
+    fs,
+    io::Write,
+    ops::RangeInclusive,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    time::Duration,
+};
+
+use crossterm::event::{
+    KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
+};
+use regex::Regex;
+
+use crate::{
+    cmdline::Config,
+    commands::{self, COMMANDS},
+    data::{
+        Block, Clipboard, FileData, FileStatus, Line, List, ListItem, Message, Pending, Prompt,
+        PromptStatus, PromptType, TextPos, BRACKETS,
+    },
+    error::{Error, Result},
+    ex::{self, ExCommand},
+    explorer::{Explorer, EXPLORER_WIDTH},
+    finder,
+    grep::Results,
+    session::Place,
+    language, paths, PREFIX_SIZE, STATUS_SIZE,
+};
+
+/// What the event loop should do after a key was handled.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    Continue,
+    Quit,
+}
+
+/// Apply one key press to the buffer, control shortcuts first, then the open prompt or the text.
+pub fn handle_key(ke: KeyEvent, w: &mut FileData, config: &mut Config) -> Outcome {
+    if !is_macro_key(ke) {
+        w.macros.record(ke);
+    }
+    run_key(ke, w, config)
+}
+
+/// handle a key without recording it, for keys pressed on behalf of the user
+fn run_key(ke: KeyEvent, w: &mut FileData, config: &mut Config) -> Outcome {
+    let quit_pending = std::mem::take(&mut w.quit_pending);
+    // an open prompt gets its editing keys before the shortcuts bound to the same keys
+    let prompt_edit = w.prompt.is_some() && is_prompt_edit_key(ke);
+    if ke.modifiers.contains(KeyModifiers::CONTROL) && !prompt_edit {
+        if let Some(outcome) = shortcut(ke, w, config, quit_pending) {
+            return outcome;
+        }
+    }
+    // F1 : command palette
+    if ke.code == KeyCode::F(1) {
+        open_palette(w);
+        return Outcome::Continue;
+    }
+    if w.ended {
+        return Outcome::Quit;
+    }
+    debug_assert_eq!(w.cursor_location.0, w.location.0, "Change before match");
+    let width = w.size.1;
+    let height = w.text_height();
+    if let Some(ref mut p) = w.prompt {
+        prompt_input(p, ke, width);
+        if p.p_type == PromptType::Finder {
+            update_preview(p, height);
+        }
+        match p.status {
+            PromptStatus::Pending => {}
+            PromptStatus::Cancelled => {
+                w.prompt = None;
+            }
+            PromptStatus::Success => {
+                if let Some(p) = w.prompt.take() {
+                    if let Err(e) = w.prompt_history.add(p.p_type, &p.data.data) {
+                        w.message = Message::with_timeout(
+                            format!("can't save prompt history: {}", e),
+                            Duration::from_secs(5),
+                        );
+                    }
+                    prompt_done(p, w, config);
+                }
+            }
+        }
+    } else if w.explorer.as_ref().is_some_and(|e| e.visible && e.focused) {
+        explorer_input(ke, w, config);
+    } else if w.results.is_some() && results_input(ke, w, config) {
+        // the results buffer used the key
+    } else {
+        normal_input(ke, w, config);
+    }
+    // a macro played from the prompt can quit
+    if w.ended {
+        Outcome::Quit
+    } else {
+        Outcome::Continue
+    }
+}
+
+/// Ctrl keys that edit the text of a prompt
+fn is_prompt_edit_key(ke: KeyEvent) -> bool {
+    ke.modifiers.contains(KeyModifiers::CONTROL)
+        && matches!(
+            ke.code,
+            KeyCode::Char('a' | 'e' | 'u' | 'k' | 'w' | 'h')
+                | KeyCode::Left
+                | KeyCode::Right
+                | KeyCode::Backspace
+                | KeyCode::Delete
+        )
+}
+
+/// open a prompt with the earlier entries of its kind to recall
+fn open_prompt<'a>(w: &'a mut FileData, message: &str, p_type: PromptType) -> &'a mut Prompt {
+    let mut p = Prompt::new(message, p_type);
+    p.history = w.prompt_history.entries(p_type).to_vec();
+    w.prompt.insert(p)
+}
+
+/// Ctrl+R and Ctrl+T control macros and are never recorded
+fn is_macro_key(ke: KeyEvent) -> bool {
+    ke.modifiers.contains(KeyModifiers::CONTROL)
+        && matches!(ke.code, KeyCode::Char('r') | KeyCode::Char('t'))
+}
+
+/// global Ctrl shortcuts, `None` for combinations the prompt or the text handle themselves
+fn shortcut(
+    ke: KeyEvent,
+    w: &mut FileData,
+    config: &mut Config,
+    quit_pending: bool,
+) -> Option<Outcome> {
+    match ke.code {
+        // Ctrl+ Q
+        KeyCode::Char('q') => {
+            let edited = std::iter::once(&*w)
+                .chain(w.buffers.iter())
+                .any(|b| b.f_status == FileStatus::Edited);
+            if edited && !quit_pending {
+                w.message = Message::with_timeout(
+                    "Press Ctrl + Q again to quit".to_owned(),
+                    Duration::from_secs(5),
+                );
+                w.quit_pending = true;
+            } else {
+                w.ended = true;
+                return Some(Outcome::Quit);
+            }
+        }
+        //Ctrl+S
+        KeyCode::Char('s') => {
+            if let Some(ref f) = config.file {
+                w.path = f.to_owned();
+                save_file(w);
+            } else {
+                open_prompt(w, "Path: ", PromptType::Save).dir = file_dir(w);
+            }
+        }
+        // Ctrl+O : open another file
+        KeyCode::Char('o') => {
+            open_prompt(w, "Open: ", PromptType::Open).dir = file_dir(w);
+        }
+        // Ctrl+Shift+F : search the files of the project
+        KeyCode::Char('f' | 'F') if ke.modifiers.contains(KeyModifiers::SHIFT) => {
+            let root = finder::project_root(&file_dir(w));
+            open_prompt(w, "Grep: ", PromptType::Grep).dir = root;
+        }
+        // Ctrl + f : Search
+        KeyCode::Char('f') => {
+            // the selected text, the word at the cursor or what was searched for last time
+            let pos = w.location;
+            let term = w
+                .selected_text()
+                .filter(|t| !t.contains('\n'))
+                .or_else(|| {
+                    let word = w.lines[pos.0].word_at(pos.1, &config.word_chars)?;
+                    Some(w.lines[pos.0].data[word].to_owned())
+                })
+                .or_else(|| w.prompt_history.last(PromptType::Search).map(str::to_owned));
+            let p = open_prompt(w, "Search: ", PromptType::Search);
+            if let Some(term) = term {
+                p.set_text(&term);
+                p.prefilled = true;
+            }
+        }
+        // Ctrl + e : re-open with another encoding
+        KeyCode::Char('e') => {
+            if w.path.as_os_str().is_empty() {
+                w.message =
+                    Message::with_timeout("No file to re-open".to_owned(), Duration::from_secs(5));
+            } else if w.f_status == FileStatus::Edited {
+                w.message = Message::with_timeout(
+                    "Save the file before re-opening it".to_owned(),
+                    Duration::from_secs(5),
+                );
+            } else {
+                open_prompt(w, "Encoding: ", PromptType::Encoding);
+            }
+        }
+        KeyCode::Char('z') => {
+            if w.undo() {
+                scroll(w);
+            } else {
+                w.message =
+                    Message::with_timeout("Nothing to undo".to_owned(), Duration::from_secs(5));
+            }
+        }
+        KeyCode::Char('y') => {
+            if w.redo() {
+                scroll(w);
+            } else {
+                w.message =
+                    Message::with_timeout("Nothing to redo".to_owned(), Duration::from_secs(5));
+            }
+        }
+        // Ctrl+Shift+D : duplicate the line or the selected lines
+        KeyCode::Char('d' | 'D') if ke.modifiers.contains(KeyModifiers::SHIFT) => {
+            line_edit(w, |w, rows| w.duplicate_lines(rows));
+        }
+        // Ctrl+D : select the word at the cursor, then add a cursor at its next occurrence
+        KeyCode::Char('d') => {
+            add_next_occurrence(w, config);
+            scroll(w);
+        }
+        // Ctrl+K or Ctrl+Shift+K : delete the line or the selected lines
+        KeyCode::Char('k' | 'K') => line_edit(w, |w, rows| w.delete_lines(rows)),
+        // Ctrl+C / Ctrl+X : copy or cut the block or the selection
+        KeyCode::Char('c' | 'x') => {
+            let copied = match w.block {
+                Some(b) => Some(Clipboard::Block(w.block_text(b))),
+                None => w.selected_text().map(Clipboard::Text),
+            };
+            if copied.is_some() {
+                w.clipboard = copied;
+                if ke.code == KeyCode::Char('x') {
+                    buffer_edit(w, |w| {
+                        if w.block.is_some() {
+                            w.delete_block();
+                        } else {
+                            w.delete_selection();
+                        }
+                    });
+                }
+            }
+        }
+        // Ctrl+V : paste, a single line of text goes on every line of a block
+        KeyCode::Char('v') => {
+            let Some(clip) = w.clipboard.clone() else {
+                return Some(Outcome::Continue);
+            };
+            buffer_edit(w, |w| match clip {
+                Clipboard::Block(lines) => {
+                    w.delete_selection();
+                    w.paste_block(&lines);
+                }
+                Clipboard::Text(text) if w.block.is_some() && !text.contains('\n') => {
+                    w.type_in_block(&text);
+                }
+                Clipboard::Text(text) => {
+                    if w.block.is_some() {
+                        w.delete_block();
+                        w.block = None;
+                    }
+                    w.delete_selection();
+                    w.insert_text(&text);
+                }
+            });
+        }
+        // Ctrl+J : join the next line, or the selected lines
+        KeyCode::Char('j') => line_edit(w, |w, rows| w.join_lines(rows)),
+        // Ctrl+/ : toggle comments, legacy terminals report it as Ctrl+7
+        KeyCode::Char('/') | KeyCode::Char('7') => {
+            let lang = language::for_path(&w.path);
+            line_edit(w, |w, rows| {
+                if !w.toggle_comment(rows, lang) {
+                    w.message = Message::with_timeout(
+                        format!("{} has no comments", lang.name),
+                        Duration::from_secs(5),
+                    );
+                }
+            });
+        }
+        // Ctrl+] : jump to the matching bracket, legacy terminals report it as Ctrl+5
+        KeyCode::Char(']') | KeyCode::Char('5') => {
+            if let Some((_, target)) = w.bracket_pair() {
+                w.anchor = None;
+                w.cursors.clear();
+                w.history.typing = false;
+                w.move_to(target);
+                scroll(w);
+            }
+        }
+        // Ctrl+Shift+P : command palette
+        KeyCode::Char('p' | 'P') if ke.modifiers.contains(KeyModifiers::SHIFT) => {
+            open_palette(w);
+        }
+        // Ctrl+B : show and focus the file explorer, hide it when it has the focus
+        KeyCode::Char('b') => match w.explorer {
+            Some(ref mut e) if e.visible && e.focused => e.visible = false,
+            Some(ref mut e) => {
+                if !e.visible {
+                    e.refresh();
+                }
+                e.visible = true;
+                e.focused = true;
+            }
+            None => {
+                w.explorer = Some(Explorer::new(PathBuf::from(".")));
+                w.message = Message::with_timeout(
+                    "Enter: open, n: new, r: rename, d: delete, i: ignored files, Esc: text"
+                        .to_owned(),
+                    Duration::from_secs(5),
+                );
+            }
+        },
+        // Ctrl+PageDown / Ctrl+PageUp : next or previous buffer
+        KeyCode::PageDown | KeyCode::PageUp => {
+            if !w.buffers.is_empty() {
+                let i = if ke.code == KeyCode::PageDown {
+                    0
+                } else {
+                    w.buffers.len() - 1
+                };
+                show_buffer(i, w, config);
+            }
+        }
+        // Ctrl+P : find a file of the project
+        KeyCode::Char('p') => {
+            let root = finder::project_root(&file_dir(w));
+            let p = open_prompt(w, "Find file: ", PromptType::Finder);
+            p.list = Some(List::default());
+            p.index = Some(finder::index(root.clone()));
+            p.dir = root;
+            poll(w);
+        }
+        // Ctrl+G : command line
+        KeyCode::Char('g') => {
+            open_prompt(w, ":", PromptType::CommandLine);
+        }
+        // Ctrl+R : start recording a macro, or stop and store it
+        KeyCode::Char('r') if !w.macros.playing => match w.macros.stop() {
+            Ok(Some((name, count))) => {
+                w.message = Message::with_timeout(
+                    format!("Recorded {} keys into macro {}", count, name),
+                    Duration::from_secs(5),
+                );
+            }
+            Ok(None) => {
+                open_prompt(w, "Record macro: ", PromptType::RecordMacro);
+            }
+            Err(e) => {
+                w.message = Message::with_timeout(
+                    format!("Recorded, but can't save macros: {}", e),
+                    Duration::from_secs(5),
+                );
+            }
+        },
+        // Ctrl+T : play a macro
+        KeyCode::Char('t') if !w.macros.playing => {
+            if w.macros.recording.is_some() {
+                w.message = Message::with_timeout(
+                    "Stop recording with Ctrl+R before playing a macro".to_owned(),
+                    Duration::from_secs(5),
+                );
+            } else {
+                open_prompt(w, "Play macro: ", PromptType::PlayMacro);
+            }
+        }
+        // other letters are not bound, they must not be typed into the text
+        KeyCode::Char(_) => {}
+        // Ctrl+Alt+Up/Down : add a cursor on the line above or below
+        KeyCode::Up | KeyCode::Down if ke.modifiers.contains(KeyModifiers::ALT) => {
+            let row = if ke.code == KeyCode::Up {
+                w.location.0.checked_sub(1)
+            } else {
+                Some(w.location.0 + 1).filter(|r| *r < w.lines.len())
+            };
+            if let Some(row) = row {
+                let col = w.same_column(row);
+                w.add_cursor(w.cursor_at(TextPos(row, col)));
+                scroll(w);
+            }
+        }
+        _ => return None,
+    }
+    Some(Outcome::Continue)
+}
+
+/// Take the files the finder found since the last call into its list, true when there were
+/// any. The event loop calls it while a finder is open to show them as they come.
+pub fn poll(w: &mut FileData) -> bool {
+    let results = poll_results(w);
+    poll_finder(w) || results
+}
+
+/// add the matches found since the last call to the results buffer
+fn poll_results(w: &mut FileData) -> bool {
+    let Some(ref mut r) = w.results else {
+        return false;
+    };
+    let done = r.search.done();
+    let new = r.search.take_new();
+    // the header changes once more when the search is over
+    if new.is_empty() && (!done || w.lines[0].data == r.header()) {
+        return false;
+    }
+    for m in new {
+        let last = r.targets.iter().rev().flatten().next();
+        if last.is_none_or(|(path, _)| *path != m.path) {
+            r.files += 1;
+        }
+        r.count += 1;
+        for line in r.lines(&m) {
+            w.lines.push(Line::from(line));
+            r.targets.push(Some((m.path.clone(), m.line)));
+        }
+        r.matches.push(m);
+    }
+    w.lines[0] = Line::from(r.header());
+    true
+}
+
+/// take the files the finder found since the last call into its list
+fn poll_finder(w: &mut FileData) -> bool {
+    let height = w.text_height();
+    let Some(ref mut p) = w.prompt else {
+        return false;
+    };
+    let Some(ref mut index) = p.index else {
+        return false;
+    };
+    let new = index.take_new();
+    if new.is_empty() {
+        return false;
+    }
+    let list = p.list.get_or_insert_with(List::default);
+    // an entry picked with Up or Down stays picked, otherwise the best one is
+    let picked = list.selected_item().filter(|_| list.selected > 0);
+    list.items.extend(new.into_iter().map(|label| ListItem {
+        label,
+        detail: String::new(),
+    }));
+    list.filter(&p.data.data);
+    if let Some(i) = picked {
+        list.selected = list.shown.iter().position(|(j, _)| *j == i).unwrap_or(0);
+    }
+    update_preview(p, height);
+    true
+}
+
+/// read the first `rows` lines of the file selected in the finder when the selection moved
+fn update_preview(p: &mut Prompt, rows: usize) {
+    let path = p
+        .list
+        .as_ref()
+        .and_then(|l| Some(p.dir.join(&l.items[l.selected_item()?].label)));
+    if p.preview.as_ref().map(|(shown, _)| shown) != path.as_ref() {
+        p.preview = path.map(|path| {
+            let lines = finder::preview(&path, rows);
+            (path, lines)
+        });
+    }
+}
+
+/// show `results` in a new read-only buffer, in place of earlier results
+fn open_results(results: Results, w: &mut FileData, config: &mut Config) {
+    let mut b = FileData::from_text(&results.header(), config, w.size);
+    b.read_only = true;
+    b.results = Some(results);
+    w.buffers.insert(0, b);
+    show_buffer(0, w, config);
+    w.buffers.retain(|b| b.results.is_none());
+    poll(w);
+}
+
+/// Keys of a results buffer: Enter opens the file at the line under the cursor and in
+/// replace mode `a` applies the replacement. False for keys it has no use for.
+fn results_input(ke: KeyEvent, w: &mut FileData, config: &mut Config) -> bool {
+    let Some(ref r) = w.results else {
+        return false;
+    };
+    match ke.code {
+        KeyCode::Enter => {
+            let Some((file, line)) = r.targets.get(w.location.0).cloned().flatten() else {
+                return true;
+            };
+            let pattern = r.pattern.clone();
+            match open_buffer(r.root.join(file), w, config) {
+                Ok(()) => {
+                    let row = min(line, w.lines.len() - 1);
+                    let col = pattern.find(&w.lines[row].data).map_or(0, |m| m.start());
+                    w.anchor = None;
+                    w.cursors.clear();
+                    w.move_to(TextPos(row, col));
+                    scroll(w);
+                }
+                Err(e) => w.message = Message::with_timeout(e.to_string(), Duration::from_secs(5)),
+            }
+            true
+        }
+        KeyCode::Char('a') if r.replacement.is_some() && ke.modifiers.is_empty() => {
+            apply_replace(w, config);
+            true
+        }
+        _ => false,
+    }
+}
+
+/// Replace the matches shown in the results, each file in its buffer as one undo step.
+/// Files that are not open get a buffer, the changes are saved like any other edit. A line
+/// that is no longer what the search found is left alone.
+fn apply_replace(w: &mut FileData, config: &mut Config) {
+    let Some(ref r) = w.results else {
+        return;
+    };
+    if !r.search.done() {
+        w.message = Message::with_timeout(
+            "Wait for the search to finish".to_owned(),
+            Duration::from_secs(5),
+        );
+        return;
+    }
+    let (root, pattern) = (r.root.clone(), r.pattern.clone());
+    let replacement = r.replacement.clone().unwrap_or_default();
+    // the matches of a file come one after another
+    let mut files: Vec<(PathBuf, Vec<(usize, String)>)> = Vec::new();
+    for m in r.matches.iter() {
+        // a buffer holds the tabs of the file as spaces unless `use_tabs` keeps them
+        let text = match config.use_tabs {
+            true => m.text.clone(),
+            false => m.text.replace('\t', &" ".repeat(config.tab_size)),
+        };
+        let path = root.join(&m.path);
+        match files.last_mut() {
+            Some((last, lines)) if *last == path => lines.push((m.line, text)),
+            _ => files.push((path, vec![(m.line, text)])),
+        }
+    }
+    let (mut count, mut changed, mut skipped) = (0, 0, 0);
+    for (path, lines) in files {
+        let i = match w.buffers.iter().position(|b| same_file(&b.path, &path)) {
+            Some(i) => i,
+            None => match FileData::from_path(&path, config.clone(), w.size) {
+                Ok(b) => {
+                    w.buffers.push(b);
+                    w.buffers.len() - 1
+                }
+                Err(e) => {
+                    w.message = Message::with_timeout(e.to_string(), Duration::from_secs(5));
+                    continue;
+                }
+            },
+        };
+        let mut n = 0;
+        buffer_edit(&mut w.buffers[i], |b| {
+            for (row, text) in lines {
+                if b.lines.get(row).is_some_and(|l| l.data == text) {
+                    n += b.substitute(row..=row, &pattern, &replacement, true);
+                } else {
+                    skipped += 1;
+                }
+            }
+        });
+        if n > 0 {
+            count += n;
+            changed += 1;
+        }
+    }
+    let skipped = match skipped {
+        0 => String::new(),
+        n => format!(", {} lines changed since the search were skipped", n),
+    };
+    w.message = Message::with_timeout(
+        format!(
+            "Replaced {} occurrences in {} buffers{}, Ctrl+Z in a buffer undoes it",
+            count, changed, skipped
+        ),
+        Duration::from_secs(5),
+    );
+}
+
+fn open_palette(w: &mut FileData) {
+    w.prompt = Some(Prompt::with_list(
+        "Command: ",
+        PromptType::Command,
+        commands::list_items(),
+    ));
+}
+
+/// select the word at the cursor, or with a selection add a cursor selecting the next
+/// occurrence of the selected text after the main cursor, which becomes the main cursor
+fn add_next_occurrence(w: &mut FileData, config: &Config) {
+    let Some((start, end)) = w.selection().filter(|(s, e)| s.0 == e.0) else {
+        let pos = w.location;
+        if let Some(word) = w.lines[pos.0].word_at(pos.1, &config.word_chars) {
+            w.anchor = Some(TextPos(pos.0, word.start));
+            w.move_to(TextPos(pos.0, word.end));
+        }
+        return;
+    };
+    let needle = w.lines[start.0].data[start.1..end.1].to_owned();
+    let taken = |w: &FileData, p: TextPos| {
+        std::iter::once(w.cursor())
+            .chain(w.cursors.iter().copied())
+            .any(|c| c.anchor.is_some_and(|a| a.min(c.location) == p))
+    };
+    // after the selection to the end of the buffer, then from the top around to it
+    let rows = (end.0..w.lines.len()).chain(0..=end.0);
+    for (n, row) in rows.enumerate() {
+        let from = if n == 0 { end.1 } else { 0 };
+        let found = w.lines[row].data[from..]
+            .match_indices(&needle)
+            .map(|(i, _)| TextPos(row, i + from))
+            .find(|p| !taken(w, *p));
+        if let Some(p) = found {
+            let mut c = w.cursor_at(TextPos(p.0, p.1 + needle.len()));
+            c.anchor = Some(p);
+            w.add_cursor(c);
+            return;
+        }
+    }
+    w.message = Message::with_timeout("No more occurrences".to_owned(), Duration::from_secs(5));
+}
+
+/// A left click moves the cursor, with Alt it adds a cursor, dragging selects text or with
+/// Alt a block, the wheel moves three lines.
+pub fn handle_mouse(me: MouseEvent, w: &mut FileData, config: &mut Config) {
+    if w.prompt.is_some() {
+        return;
+    }
+    let in_pane = w.text_left() > PREFIX_SIZE && usize::from(me.column) < EXPLORER_WIDTH;
+    match me.kind {
+        MouseEventKind::Down(MouseButton::Left) if in_pane => {
+            explorer_click(me.row.into(), w, config);
+        }
+        MouseEventKind::Down(MouseButton::Left) => {
+            if let Some(ref mut e) = w.explorer {
+                e.focused = false;
+            }
+            let Some(cell) = cell_at(w, me.row.into(), me.column.into()) else {
+                return;
+            };
+            let c = w.cursor_at(text_pos(w, cell));
+            w.block = None;
+            if me.modifiers.contains(KeyModifiers::ALT) {
+                w.add_cursor(c);
+            } else {
+                w.cursors.clear();
+                w.set_cursor(c);
+            }
+            w.history.typing = false;
+            scroll(w);
+        }
+        MouseEventKind::Drag(MouseButton::Left) => {
+            let Some(cell) = cell_at(w, me.row.into(), me.column.into()) else {
+                return;
+            };
+            w.cursors.clear();
+            if me.modifiers.contains(KeyModifiers::ALT) {
+                // the block starts where the drag did, at the column that was clicked
+                let start = TextPos(w.location.0, w.cursor_location.1);
+                let anchor = w.block.map_or(start, |b| b.anchor);
+                w.anchor = None;
+                w.block = Some(Block { anchor, head: cell });
+                let pos = text_pos(w, cell);
+                w.move_to(pos);
+            } else {
+                w.block = None;
+                w.anchor.get_or_insert(w.location);
+                let pos = text_pos(w, cell);
+                w.move_to(pos);
+            }
+            w.history.typing = false;
+            scroll(w);
+        }
+        MouseEventKind::ScrollUp | MouseEventKind::ScrollDown => {
+            let code = if me.kind == MouseEventKind::ScrollUp {
+                KeyCode::Up
+            } else {
+                KeyCode::Down
+            };
+            for _ in 0..3 {
+                normal_input(KeyEvent::new(code, KeyModifiers::NONE), w, config);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// (line, display column) shown at a screen cell, the column may be past the end of the
+/// line, `None` outside of the text rows
+fn cell_at(w: &FileData, row: usize, col: usize) -> Option<TextPos> {
+    let row = row.checked_sub(STATUS_SIZE)?;
+    if row >= w.text_height() {
+        return None;
+    }
+    let line = min(w.top_visible + row, w.lines.len() - 1);
+    Some(TextPos(line, col.saturating_sub(w.text_left()) + w.left_visible))
+}
+
+/// the text position of a (line, display column) cell, clamped to the line
+fn text_pos(w: &FileData, cell: TextPos) -> TextPos {
+    TextPos(cell.0, w.lines[cell.0].display_pos(cell.1, w.tab_size))
+}
+
+/// run the action of a prompt that was confirmed with Enter
+fn prompt_done(p: Prompt, w: &mut FileData, config: &mut Config) {
+    match p.p_type {
+        PromptType::Save => save_to(paths::resolve(&p.dir, p.data.data.trim()), w, config),
+        PromptType::Open => {
+            if let Err(e) = open_file(paths::resolve(&p.dir, p.data.data.trim()), w, config) {
+                w.message = Message::with_timeout(e.to_string(), Duration::from_secs(5));
+            }
+        }
+        PromptType::Confirm if p.data.data == "y" => match p.pending {
+            Some(Pending::Save(path)) => write_to(path, w, config),
+            Some(Pending::Rename(from, to)) => rename(from, to, w, config),
+            Some(Pending::Delete(path)) => delete(path, w),
+            None => {}
+        },
+        PromptType::Confirm => {}
+        PromptType::Grep => match Regex::new(&p.data.data) {
+            Ok(pattern) => open_results(Results::new(p.dir, pattern, None), w, config),
+            Err(e) => w.message = Message::with_timeout(e.to_string(), Duration::from_secs(5)),
+        },
+        PromptType::Finder => {
+            let picked = p.list.and_then(|l| Some(l.items[l.selected_item()?].label.clone()));
+            let opened = match picked {
+                Some(file) => open_buffer(p.dir.join(file), w, config),
+                None => Err(Error::Command("No matching file".to_owned())),
+            };
+            if let Err(e) = opened {
+                w.message = Message::with_timeout(e.to_string(), Duration::from_secs(5));
+            }
+        }
+        PromptType::NewFile => create_file(&p.dir, &p.data.data, w),
+        PromptType::Rename => {
+            let from = w.explorer.as_ref().and_then(|e| Some(e.selected_entry()?.path.clone()));
+            let to = paths::resolve(&p.dir, p.data.data.trim());
+            match from {
+                Some(from) if from == to => {}
+                Some(from) if to.exists() => {
+                    let question = format!("Overwrite {}? (y/n)", to.display());
+                    let p = w.prompt.insert(Prompt::new(&question, PromptType::Confirm));
+                    p.pending = Some(Pending::Rename(from, to));
+                }
+                Some(from) => rename(from, to, w, config),
+                None => {}
+            }
+        }
+        PromptType::Search => search(w, &p.data.data),
+        PromptType::RecordMacro => {
+            w.macros.start(p.data.data.trim());
+            if let Some((ref name, _)) = w.macros.recording {
+                w.message = Message::with_timeout(
+                    format!("Recording macro {}, Ctrl+R to stop", name),
+                    Duration::from_secs(5),
+                );
+            }
+        }
+        PromptType::PlayMacro => play_prompt(&p.data.data, w, config),
+        PromptType::CommandLine => {
+            let line = p.data.data.trim();
+            if line.is_empty() {
+                return;
+            }
+            match ex::parse(line).and_then(|cmd| command_line(cmd, w, config)) {
+                Ok(Some(text)) => {
+                    w.message = Message::with_timeout(text, Duration::from_secs(5));
+                }
+                Ok(None) => {}
+                Err(e) => w.message = Message::with_timeout(e.to_string(), Duration::from_secs(5)),
+            }
+        }
+        PromptType::Command => {
+            if let Some(i) = p.list.and_then(|l| l.selected_item()) {
+                run_key(COMMANDS[i].key, w, config);
+            }
+        }
+        PromptType::Encoding => {
+            let label = p.data.data.trim().to_owned();
+            let reopened = encoding_rs::Encoding::for_label(label.as_bytes())
+                .ok_or(Error::UnknownEncoding(label))
+                .and_then(|enc| w.reopen(enc, config).map(|_| enc.name()));
+            w.message = Message::with_timeout(
+                match reopened {
+                    Ok(name) => format!("Re-opened as {}", name),
+                    Err(e) => e.to_string(),
+                },
+                Duration::from_secs(5),
+            );
+        }
+    }
+}
+
+/// directory of the file in the buffer, where typed relative paths start
+fn file_dir(w: &FileData) -> PathBuf {
+    w.path.parent().map(PathBuf::from).unwrap_or_default()
+}
+
+/// Save to a path typed by the user, asking first when that replaces another file or
+/// needs new directories.
+fn save_to(path: PathBuf, w: &mut FileData, config: &mut Config) {
+    let parent = path.parent().filter(|p| !p.as_os_str().is_empty());
+    let question = match parent {
+        Some(dir) if !dir.is_dir() => format!("Create {}? (y/n)", dir.display()),
+        _ if path.is_file() && path != w.path => format!("Overwrite {}? (y/n)", path.display()),
+        _ => return write_to(path, w, config),
+    };
+    let p = w.prompt.insert(Prompt::new(&question, PromptType::Confirm));
+    p.pending = Some(Pending::Save(path));
+}
+
+/// save to `path` from now on, creating the directories leading to it
+fn write_to(path: PathBuf, w: &mut FileData, config: &mut Config) {
+    if let Some(dir) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        if let Err(source) = fs::create_dir_all(dir) {
+            w.message = Message::with_timeout(
+                Error::Save { path, source }.to_string(),
+                Duration::from_secs(5),
+            );
+            return;
+        }
+    }
+    config.file = Some(path.clone());
+    w.path = path;
+    save_file(w);
+}
+
+/// replace the buffer with the file at `path`, refused while the buffer has unsaved changes
+fn open_file(path: PathBuf, w: &mut FileData, config: &mut Config) -> Result<()> {
+    if w.f_status == FileStatus::Edited {
+        return Err(Error::Command(
+            "Save the file before opening another".to_owned(),
+        ));
+    }
+    let mut opened = FileData::from_path(&path, config.clone(), w.size)?;
+    let left = Place::of(w);
+    w.session.remember(&w.path, left);
+    opened.take_session(w);
+    if let Some(place) = opened.session.place(&path) {
+        place.apply(&mut opened);
+    }
+    *w = opened;
+    config.file = Some(path);
+    Ok(())
+}
+
+/// Show the file at `path` in a buffer of its own, switching to it when it is open already.
+/// The buffer that was shown stays open as the previous one.
+fn open_buffer(path: PathBuf, w: &mut FileData, config: &mut Config) -> Result<()> {
+    if same_file(&w.path, &path) {
+        return Ok(());
+    }
+    match w.buffers.iter().position(|b| same_file(&b.path, &path)) {
+        Some(i) => show_buffer(i, w, config),
+        None => {
+            let mut opened = FileData::from_path(&path, config.clone(), w.size)?;
+            if let Some(place) = w.session.place(&path) {
+                place.apply(&mut opened);
+            }
+            w.buffers.insert(0, opened);
+            show_buffer(0, w, config);
+        }
+    }
+    Ok(())
+}
+
+/// Bring back the buffers open when the last session ended. The one shown then is shown
+/// again, unless a file was opened already.
+pub fn restore_session(w: &mut FileData, config: &mut Config) {
+    let named = Some(w.path.clone()).filter(|p| !p.as_os_str().is_empty());
+    let files = w.session.open_files();
+    let mut failed = 0;
+    for path in files.iter() {
+        if open_buffer(path.clone(), w, config).is_err() {
+            failed += 1;
+        }
+    }
+    if let Some(shown) = named.or(files.into_iter().next()) {
+        let _ = open_buffer(shown, w, config);
+    }
+    if failed > 0 {
+        w.message = Message::with_timeout(
+            format!("{} files of the last session could not be opened", failed),
+            Duration::from_secs(5),
+        );
+    }
+}
+
+fn same_file(a: &Path, b: &Path) -> bool {
+    a == b
+        || !a.as_os_str().is_empty()
+            && matches!((a.canonicalize(), b.canonicalize()), (Ok(a), Ok(b)) if a == b)
+}
+
+/// Switch to buffer `i` of `w.buffers`. The buffers form a ring with the shown one, which
+/// keeps its place in it, so the ones after `i` come next. An empty buffer without a file
+/// is dropped instead.
+fn show_buffer(i: usize, w: &mut FileData, config: &mut Config) {
+    w.buffers.rotate_left(i);
+    let mut next = w.buffers.remove(0);
+    let place = w.buffers.len() - i;
+    next.take_session(w);
+    let prev = std::mem::replace(w, next);
+    let scratch = prev.path.as_os_str().is_empty()
+        && prev.f_status == FileStatus::Clean
+        && prev.lines.len() == 1
+        && prev.lines[0].data.is_empty();
+    if !scratch {
+        w.buffers.insert(place, prev);
+    }
+    w.prompt = None;
+    config.file = Some(w.path.clone()).filter(|p| !p.as_os_str().is_empty());
+    scroll(w);
+}
+
+/// Keys while the explorer has the focus: arrows move through the tree and open or close
+/// folders, Enter opens the selected file, letters manage files.
+fn explorer_input(ke: KeyEvent, w: &mut FileData, config: &mut Config) {
+    let height = w.text_height();
+    let Some(ref mut e) = w.explorer else {
+        return;
+    };
+    match ke.code {
+        KeyCode::Up => e.select(-1),
+        KeyCode::Down => e.select(1),
+        KeyCode::PageUp => e.select(-(height as isize)),
+        KeyCode::PageDown => e.select(height as isize),
+        KeyCode::Home => e.selected = 0,
+        KeyCode::End => e.select(isize::MAX),
+        KeyCode::Right => e.expand(),
+        KeyCode::Left => e.collapse(),
+        KeyCode::Esc | KeyCode::Tab => e.focused = false,
+        KeyCode::Enter => {
+            if !e.toggle() {
+                explorer_open(w, config);
+            }
+        }
+        KeyCode::Char('i') => {
+            e.show_ignored = !e.show_ignored;
+            e.refresh();
+        }
+        KeyCode::Char('n') => {
+            let dir = e.target_dir();
+            open_prompt(w, "New file: ", PromptType::NewFile).dir = dir;
+        }
+        KeyCode::Char('r') | KeyCode::F(2) => {
+            if let Some(entry) = e.selected_entry() {
+                let dir = entry.path.parent().map(PathBuf::from).unwrap_or_default();
+                let name = entry.name();
+                let p = open_prompt(w, "Rename: ", PromptType::Rename);
+                p.dir = dir;
+                p.set_text(&name);
+            }
+        }
+        KeyCode::Char('d') | KeyCode::Delete => {
+            if let Some(entry) = e.selected_entry() {
+                let path = entry.path.clone();
+                let question = format!("Delete {}? (y/n)", path.display());
+                let p = w.prompt.insert(Prompt::new(&question, PromptType::Confirm));
+                p.pending = Some(Pending::Delete(path));
+            }
+        }
+        _ => {}
+    }
+    if let Some(ref mut e) = w.explorer {
+        e.scroll(height);
+    }
+}
+
+/// A click on the explorer selects the entry under it and focuses the tree, a folder opens
+/// or closes and a file opens in its buffer.
+fn explorer_click(row: usize, w: &mut FileData, config: &mut Config) {
+    let Some(ref mut e) = w.explorer else {
+        return;
+    };
+    e.focused = true;
+    let Some(n) = row.checked_sub(STATUS_SIZE).map(|r| r + e.top) else {
+        return;
+    };
+    if n < e.entries.len() {
+        e.selected = n;
+        if !e.toggle() {
+            explorer_open(w, config);
+        }
+    }
+}
+
+/// open the file selected in the explorer and give the focus back to the text
+fn explorer_open(w: &mut FileData, config: &mut Config) {
+    let Some(path) = w.explorer.as_ref().and_then(|e| Some(e.selected_entry()?.path.clone()))
+    else {
+        return;
+    };
+    match open_buffer(path, w, config) {
+        Ok(()) => {
+            if let Some(ref mut e) = w.explorer {
+                e.focused = false;
+            }
+        }
+        Err(e) => w.message = Message::with_timeout(e.to_string(), Duration::from_secs(5)),
+    }
+}
+
+/// create the file or, with a trailing `/`, the folder typed into the new file prompt
+fn create_file(dir: &Path, text: &str, w: &mut FileData) {
+    let path = paths::resolve(dir, text.trim());
+    let created = if text.trim_end().ends_with('/') {
+        fs::create_dir_all(&path)
+    } else {
+        path.parent()
+            .filter(|p| !p.as_os_str().is_empty())
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|_| fs::OpenOptions::new().write(true).create_new(true).open(&path))
+            .map(|_| ())
+    };
+    match created {
+        Ok(()) => explorer_changed(w, Some(&path)),
+        Err(e) => {
+            w.message = Message::with_timeout(
+                format!("can't create {}: {}", path.display(), e),
+                Duration::from_secs(5),
+            );
+        }
+    }
+}
+
+/// move a file or folder, the buffers of files under it follow
+fn rename(from: PathBuf, to: PathBuf, w: &mut FileData, config: &mut Config) {
+    if let Err(e) = fs::rename(&from, &to) {
+        w.message = Message::with_timeout(
+            format!("can't rename {}: {}", from.display(), e),
+            Duration::from_secs(5),
+        );
+        return;
+    }
+    let follow = |path: &mut PathBuf| {
+        if let Ok(rest) = path.strip_prefix(&from) {
+            *path = to.join(rest);
+        }
+    };
+    follow(&mut w.path);
+    w.buffers.iter_mut().for_each(|b| follow(&mut b.path));
+    if let Some(ref mut file) = config.file {
+        follow(file);
+    }
+    explorer_changed(w, Some(&to));
+}
+
+/// remove a file, or a folder with everything in it, open buffers keep their text
+fn delete(path: PathBuf, w: &mut FileData) {
+    let removed = if path.is_dir() {
+        fs::remove_dir_all(&path)
+    } else {
+        fs::remove_file(&path)
+    };
+    match removed {
+        Ok(()) => explorer_changed(w, None),
+        Err(e) => {
+            w.message = Message::with_timeout(
+                format!("can't delete {}: {}", path.display(), e),
+                Duration::from_secs(5),
+            );
+        }
+    }
+}
+
+/// read the tree again after a file changed, selecting `path`
+fn explorer_changed(w: &mut FileData, path: Option<&Path>) {
+    let height = w.text_height();
+    if let Some(ref mut e) = w.explorer {
+        e.refresh();
+        if let Some(path) = path {
+            e.select_path(path);
+        }
+        e.scroll(height);
+    }
+}
+
+/// run a command from the command line, `Some` text to show in the message line
+fn command_line(cmd: ExCommand, w: &mut FileData, config: &mut Config) -> Result<Option<String>> {
+    match cmd {
+        ExCommand::Write(Some(path)) => {
+            save_to(path, w, config);
+            Ok(None)
+        }
+        ExCommand::Write(None) => match config.file.clone() {
+            Some(path) => {
+                write_to(path, w, config);
+                Ok(None)
+            }
+            None => Err(Error::Command("no file name, use w path".to_owned())),
+        },
+        ExCommand::Edit(path) => open_file(path, w, config).map(|_| None),
+        ExCommand::Set(name, value) => {
+            let shown = config.set(&name, value.as_deref());
+            w.tab_size = config.tab_size.max(1);
+            for b in w.buffers.iter_mut() {
+                b.tab_size = w.tab_size;
+            }
+            shown
+        }
+        ExCommand::Goto(line) => {
+            w.anchor = None;
+            w.cursors.clear();
+            w.history.typing = false;
+            w.move_to(TextPos(min(line, w.lines.len() - 1), 0));
+            scroll(w);
+            Ok(None)
+        }
+        ExCommand::Substitute {
+            pattern,
+            replacement,
+            global,
+        } => {
+            let mut count = 0;
+            buffer_edit(w, |w| {
+                let rows = w.target_rows();
+                count = w.substitute(rows, &pattern, &replacement, global);
+            });
+            Ok(Some(match count {
+                0 => format!("Pattern not found: {}", pattern),
+                n => format!("Replaced {} occurrences", n),
+            }))
+        }
+        ExCommand::Sort => {
+            // a selection of one line sorts nothing, the whole buffer is meant
+            let rows = w
+                .selected_rows()
+                .filter(|r| r.start() != r.end())
+                .unwrap_or(0..=w.lines.len() - 1);
+            buffer_edit(w, |w| w.sort_lines(rows));
+            Ok(None)
+        }
+        ExCommand::Shell(cmd) => shell(&cmd, w),
+        ExCommand::Grep(pattern) => {
+            let root = finder::project_root(&file_dir(w));
+            open_results(Results::new(root, pattern, None), w, config);
+            Ok(None)
+        }
+        ExCommand::Replace {
+            pattern,
+            replacement,
+        } => {
+            let root = finder::project_root(&file_dir(w));
+            open_results(Results::new(root, pattern, Some(replacement)), w, config);
+            Ok(None)
+        }
+    }
+}
+
+/// Run `cmd` with `sh -c`. The selected lines are piped through it and replaced with what
+/// it prints, without a selection the last line it printed is shown.
+fn shell(cmd: &str, w: &mut FileData) -> Result<Option<String>> {
+    let failed = |e: std::io::Error| Error::Command(format!("can't run {}: {}", cmd, e));
+    let rows = w.selected_rows();
+    let mut child = Command::new("sh")
+        .arg("-c")
+        .arg(cmd)
+        .stdin(if rows.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(failed)?;
+    if let (Some(rows), Some(mut stdin)) = (rows.clone(), child.stdin.take()) {
+        let input: String = w.lines[rows].iter().map(|l| l.data.clone() + "\n").collect();
+        // a command that does not read its input closes the pipe early, that is fine
+        let _ = stdin.write_all(input.as_bytes());
+    }
+    let output = child.wait_with_output().map_err(failed)?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let reason = stderr.lines().last().unwrap_or_default().to_owned();
+        return Err(Error::Command(format!("{} failed: {}", cmd, reason)));
+    }
+    match rows {
+        Some(rows) => {
+            if w.read_only {
+                return Err(Error::Command("Buffer is read-only".to_owned()));
+            }
+            let lines = stdout.lines().map(Line::from).collect();
+            buffer_edit(w, |w| w.replace_lines(rows, lines));
+            Ok(None)
+        }
+        None => Ok(Some(stdout.lines().last().unwrap_or_default().to_owned())),
+    }
+}
+
+/// play the macro asked for in the prompt, `[name] [times]` where `*` as the number of
+/// times repeats it until the end of the buffer and no name plays the last one recorded
+fn play_prompt(text: &str, w: &mut FileData, config: &mut Config) {
+    let mut words: Vec<&str> = text.split_whitespace().collect();
+    let times = match words.last().map(|n| (*n, n.parse())) {
+        Some(("*", _)) => None,
+        Some((_, Ok(n))) => Some(n),
+        _ => Some(1),
+    };
+    if words.last().is_some_and(|n| *n == "*" || n.parse::<usize>().is_ok()) {
+        words.pop();
+    }
+    let name = match words.join(" ") {
+        name if name.is_empty() => w.macros.last.clone().unwrap_or_default(),
+        name => name,
+    };
+    match w.macros.get(&name).map(|k| k.to_vec()) {
+        Some(keys) => {
+            let runs = play_macro(&keys, times, w, config);
+            if !w.ended {
+                w.message = Message::with_timeout(
+                    format!("Played macro {} {} times", name, runs),
+                    Duration::from_secs(5),
+                );
+            }
+        }
+        None => {
+            w.message = Message::with_timeout(
+                format!("No macro named \"{}\"", name),
+                Duration::from_secs(5),
+            );
+        }
+    }
+}
+
+/// Feed `keys` to the buffer `times` times, with `None` until a run ends on the line it
+/// started on, so a macro that finishes by moving down stops after the last line. `None`
+/// runs at most once for each line from the cursor down as they were at the start, a
+/// macro adding lines would never get there. Returns how many times it ran.
+pub fn play_macro(
+    keys: &[KeyEvent],
+    times: Option<usize>,
+    w: &mut FileData,
+    config: &mut Config,
+) -> usize {
+    w.macros.playing = true;
+    let mut runs = 0;
+    let limit = times.unwrap_or(w.lines.len() - w.location.0);
+    while runs < limit {
+        let row = w.location.0;
+        for ke in keys {
+            if handle_key(*ke, w, config) == Outcome::Quit {
+                w.macros.playing = false;
+                return runs + 1;
+            }
+        }
+        runs += 1;
+        if times.is_none() && w.location.0 <= row {
+            break;
+        }
+    }
+    w.macros.playing = false;
+    runs
+}
+
+/// move to the next occurrence of `text` after the cursor, wrapping around to the top
+pub fn search(w: &mut FileData, text: &str) {
+    // the match at the cursor is the one found last time, it comes last
+    let pos = TextPos(w.location.0, w.get_next_and_prev_chars().1);
+    let mut find = None;
+    if let Some(f) = w.lines[pos.0].data[pos.1..].find(text) {
+        find = Some((pos.0, f + pos.1));
+    }
+    if find.is_none() {
+        for (idx, l) in w.lines[pos.0 + 1..].iter().enumerate() {
+            if let Some(f) = l.data.find(text) {
+                find = Some((idx + pos.0 + 1, f));
+                break;
+            }
+        }
+    }
+    if find.is_none() {
+        for (idx, l) in w.lines[..=pos.0].iter().enumerate() {
+            if let Some(f) = l.data.find(text) {
+                find = Some((idx, f));
+                break;
+            }
+        }
+    }
+
+    //scroll
+    w.anchor = None;
+    w.cursors.clear();
+    w.history.typing = false;
+    if let Some(loc) = find {
+        w.location.0 = loc.0;
+        w.location.1 = loc.1;
+        w.cursor_location.0 = loc.0;
+        // get char index of loc.1
+        let mut count = 0;
+        for (id, _) in w.lines[loc.0].data.char_indices() {
+            if loc.1 == id {
+                break;
+            }
+            count += 1
+        }
+        w.cursor_location.1 = count;
+        w.message = Message::with_timeout(
+            format!(
+                "Found: \"{}\" at Ln:{}, Col:{}",
+                text, loc.0, w.cursor_location.1
+            ),
+            Duration::from_secs(5),
+        )
+    } else {
+        w.message = Message::with_timeout(
+            format!("Phrase: \"{}\" not found", text),
+            Duration::from_secs(5),
+        )
+    }
+    scroll(w);
+}
+
+pub fn scroll(w: &mut FileData) {
+    if w.location.0 < w.top_visible {
+        w.top_visible = w.location.0
+    } else if w.location.0 >= w.top_visible + w.text_height() {
+        w.top_visible = w.location.0 + 1 - w.text_height()
+    }
+    // a block can reach past the end of the line, its head is where the cursor is drawn
+    let col = w.block.map_or(w.display_col(), |b| b.head.1);
+    if col < w.left_visible {
+        w.left_visible = col;
+    } else if col >= w.left_visible + w.text_width() {
+        w.left_visible = col + 1 - w.text_width()
+    }
+    assert_eq!(
+        w.cursor_location.0, w.location.0,
+        "Different height of cursor and string pointer"
+    )
+}
+fn prompt_scroll(prompt: &mut Prompt, size: usize) {
+    if prompt.cursor < prompt.left_visible {
+        prompt.left_visible = prompt.cursor;
+    } else if prompt.cursor >= prompt.left_visible + size - prompt.message.char_len {
+        // eprintln!("move right");
+        prompt.left_visible = prompt.cursor + 1 + prompt.message.char_len - size
+    }
+}
+
+pub fn normal_input(ke: KeyEvent, w: &mut FileData, config: &mut Config) {
+    let ctrl = ke.modifiers.contains(KeyModifiers::CONTROL);
+    let editing = matches!(
+        ke.code,
+        KeyCode::Backspace
+            | KeyCode::Enter
+            | KeyCode::Tab
+            | KeyCode::BackTab
+            | KeyCode::Delete
+            | KeyCode::Char(_)
+    );
+    if w.read_only && editing {
+        w.message =
+            Message::with_timeout("Buffer is read-only".to_owned(), Duration::from_secs(5));
+        return;
+    }
+    // Alt+Shift+arrows : start or grow a block
+    if ke.modifiers == KeyModifiers::ALT | KeyModifiers::SHIFT && extend_block(ke.code, w) {
+        return;
+    }
+    // Alt+Up/Down : move the line or the selected lines
+    if ke.modifiers == KeyModifiers::ALT && matches!(ke.code, KeyCode::Up | KeyCode::Down) {
+        line_edit(w, |w, rows| {
+            w.move_lines(rows, ke.code == KeyCode::Up);
+        });
+        return;
+    }
+    let moving = matches!(
+        ke.code,
+        KeyCode::Left
+            | KeyCode::Right
+            | KeyCode::Up
+            | KeyCode::Down
+            | KeyCode::Home
+            | KeyCode::End
+            | KeyCode::PageUp
+            | KeyCode::PageDown
+    );
+    if ke.code == KeyCode::Esc {
+        w.cursors.clear();
+        w.anchor = None;
+        w.block = None;
+        return;
+    }
+    if w.block.is_some() {
+        if !matches!(ke.code, KeyCode::Char(_) | KeyCode::Backspace | KeyCode::Delete) {
+            // any other key leaves the block and acts on the cursor
+            w.block = None;
+        } else {
+            w.checkpoint();
+            w.history.typing = false;
+            match ke.code {
+                KeyCode::Char(c) => w.type_in_block(&c.to_string()),
+                code => w.erase_block(code == KeyCode::Backspace),
+            }
+            w.drop_unchanged_checkpoint();
+            scroll(w);
+            return;
+        }
+    }
+    // moves by pages and to the ends of the buffer leave one cursor
+    if matches!(ke.code, KeyCode::PageUp | KeyCode::PageDown)
+        || (ctrl && matches!(ke.code, KeyCode::Home | KeyCode::End))
+    {
+        w.cursors.clear();
+    }
+    let mut checkpoint = false;
+    if editing {
+        let typing = matches!(ke.code, KeyCode::Char(_));
+        checkpoint = !(typing && w.history.typing && w.anchor.is_none());
+        if checkpoint {
+            w.checkpoint();
+        }
+        w.history.typing = typing;
+    } else if moving {
+        w.history.typing = false;
+    }
+    if w.cursors.is_empty() {
+        cursor_input(ke, w, config);
+    } else {
+        // bottom cursors first, an edit only changes text before the cursors already done
+        let main = w.cursor();
+        let mut all = std::mem::take(&mut w.cursors);
+        all.push(main);
+        all.sort_by_key(|c| std::cmp::Reverse(c.location));
+        let mut done = Vec::with_capacity(all.len());
+        for c in all {
+            w.set_cursor(c);
+            cursor_input(ke, w, config);
+            let anchor = w.anchor.map(|a| w.to_tail(a));
+            done.push((w.to_tail(w.location), anchor, c == main));
+        }
+        for (location, anchor, is_main) in done {
+            let mut c = w.cursor_at(w.from_tail(location));
+            c.anchor = anchor.map(|a| w.from_tail(a));
+            if is_main {
+                w.set_cursor(c);
+            } else {
+                w.cursors.push(c);
+            }
+        }
+        w.merge_cursors();
+    }
+    if checkpoint {
+        w.drop_unchanged_checkpoint();
+    }
+    scroll(w);
+}
+
+/// apply a key at the main cursor
+fn cursor_input(ke: KeyEvent, w: &mut FileData, config: &Config) {
+    let tab = indent_unit(config);
+    let tab_size = config.tab_size.max(1);
+    let ctrl = ke.modifiers.contains(KeyModifiers::CONTROL);
+    if matches!(
+        ke.code,
+        KeyCode::Left
+            | KeyCode::Right
+            | KeyCode::Up
+            | KeyCode::Down
+            | KeyCode::Home
+            | KeyCode::End
+            | KeyCode::PageUp
+            | KeyCode::PageDown
+    ) {
+        // Shift extends the selection from where the cursor was, other movement drops it
+        if ke.modifiers.contains(KeyModifiers::SHIFT) {
+            w.anchor.get_or_insert(w.location);
+        } else {
+            w.anchor = None;
+        }
+    }
+    if matches!(
+        ke.code,
+        KeyCode::Backspace
+            | KeyCode::Enter
+            | KeyCode::Tab
+            | KeyCode::BackTab
+            | KeyCode::Delete
+            | KeyCode::Char(_)
+    ) {
+        if let (Some(rows), KeyCode::Tab | KeyCode::BackTab) = (w.selected_rows(), ke.code) {
+            indent_rows(w, rows, ke.code == KeyCode::Tab, config);
+            return;
+        }
+        // typed text replaces the selection, Backspace and Delete only remove it
+        let removed = ke.code != KeyCode::BackTab && w.delete_selection();
+        w.anchor = None;
+        if removed && matches!(ke.code, KeyCode::Backspace | KeyCode::Delete) {
+            return;
+        }
+    }
+    let (prev_char_bound, next_char_bound) = w.get_next_and_prev_chars();
+    let pos = w.location;
+    let prev_char = w.lines[pos.0].data[..pos.1].chars().next_back();
+    let next_char = w.lines[pos.0].data[pos.1..].chars().next();
+    let in_pair = config
+        .auto_pairs
+        .iter()
+        .any(|p| Some(p.0) == prev_char && Some(p.1) == next_char);
+    match ke.code {
+        KeyCode::Backspace if ctrl && pos.1 > 0 => {
+            let start = w.lines[pos.0].prev_word_boundary(pos.1, &config.word_chars);
+            w.lines[pos.0].remove_range(start..pos.1);
+            w.move_to(TextPos(pos.0, start));
+            w.f_status = FileStatus::Edited;
+        } // delete to the start of the word, at the start of the line fall through to Backspace
+        KeyCode::Backspace if in_pair => {
+            w.lines[pos.0].remove_range(prev_char_bound..next_char_bound);
+            w.move_to(TextPos(pos.0, prev_char_bound));
+            w.f_status = FileStatus::Edited;
+        } // remove both characters of an empty pair
+        KeyCode::Backspace if prev_char == Some(' ') && w.lines[pos.0].indent_len() >= pos.1 => {
+            let before = &w.lines[pos.0].data[..pos.1];
+            let spaces = before.len() - before.trim_end_matches(' ').len();
+            let width = min((w.display_col() - 1) % tab_size + 1, spaces);
+            let start = pos.1 - width;
+            w.lines[pos.0].remove_range(start..pos.1);
+            w.move_to(TextPos(pos.0, start));
+            w.f_status = FileStatus::Edited;
+        } // in the indentation remove spaces back to the previous tab stop
+        KeyCode::Backspace => {
+            if pos.1 == 0 && pos.0 > 0 {
+                // calculate new position of the cursor
+                let n_pos = TextPos(pos.0 - 1, w.lines[pos.0 - 1].len());
+                let line = w.lines[pos.0].clone();
+                w.cursor_location.1 = w.lines[pos.0 - 1].char_len;
+                w.lines[pos.0 - 1].push_str(&line.data); // append line contents
+                w.lines.remove(pos.0); // remove old line
+                w.location = n_pos;
+
+                w.cursor_location.0 = pos.0 - 1;
+                w.f_status = FileStatus::Edited;
+            } else if pos.1 > 0 {
+                w.lines[pos.0].remove(prev_char_bound); // remove previous character
+                w.location.1 = prev_char_bound; // move cursor backward
+                w.cursor_location.1 -= 1;
+                w.f_status = FileStatus::Edited;
+            }
+        } // delete previous character if at start of line merge current into previous
+        KeyCode::Enter => {
+            let before = &w.lines[pos.0].data[..pos.1];
+            let base = before[..before.len() - before.trim_start().len()].to_owned();
+            let opener = before.trim_end().chars().next_back();
+            let mut indent = base.clone();
+            if opener.is_some_and(|c| language::for_path(&w.path).indent_after.contains(&c)) {
+                indent.push_str(&tab);
+            }
+            let mut new_line = w.lines[pos.0].split_at(pos.1);
+            new_line.remove_range(0..new_line.indent_len());
+            // Enter between a pair of brackets puts the closing one on its own line
+            if indent != base
+                && BRACKETS
+                    .iter()
+                    .any(|b| Some(b.0) == opener && new_line.data.starts_with(b.1))
+            {
+                new_line.insert_str(0, &base);
+                w.lines.insert(pos.0 + 1, new_line);
+                new_line = Line::from("");
+            }
+            new_line.insert_str(0, &indent);
+            w.lines.insert(pos.0 + 1, new_line);
+            w.move_to(TextPos(pos.0 + 1, indent.len()));
+            w.f_status = FileStatus::Edited;
+        } // insert new line behind current with the indentation of the current one
+        KeyCode::Left if ctrl && pos.1 > 0 => {
+            let start = w.lines[pos.0].prev_word_boundary(pos.1, &config.word_chars);
+            w.move_to(TextPos(pos.0, start));
+        } // go to the start of the word, at the start of the line fall through to Left
+        KeyCode::Right if ctrl && pos.1 < w.lines[pos.0].len() => {
+            let end = w.lines[pos.0].next_word_boundary(pos.1, &config.word_chars);
+            w.move_to(TextPos(pos.0, end));
+        } // go to the end of the word, at the end of the line fall through to Right
+        KeyCode::Left => {
+            if pos.1 > 0 {
+                w.location.1 = prev_char_bound;
+                w.cursor_location.1 -= 1;
+            } else if pos.0 > 0 {
+                w.location.0 -= 1;
+                w.location.1 = w.lines[w.location.0].len();
+                w.cursor_location.0 -= 1;
+                w.cursor_location.1 = w.lines[w.location.0].char_len;
+            }
+        } // go to the left if at the start of the line go to end of previous
+        KeyCode::Right => {
+            if pos.1 < w.lines[pos.0].len() {
+                w.location.1 = next_char_bound;
+                w.cursor_location.1 += 1;
+            } else if pos.0 < w.lines.len() - 1 {
+                w.location.1 = 0;
+                w.location.0 += 1;
+                w.cursor_location = w.location.to_owned();
+            }
+        } // go to the right if at the end of the line go to start of next
+        KeyCode::Up => {
+            if pos.0 > 0 {
+                let col = w.same_column(pos.0 - 1);
+                w.move_to(TextPos(pos.0 - 1, col));
+            }
+        } // go one line up
+        KeyCode::Down => {
+            if pos.0 < w.lines.len() - 1 {
+                let col = w.same_column(pos.0 + 1);
+                w.move_to(TextPos(pos.0 + 1, col));
+            }
+        } // go one line down
+        KeyCode::PageDown => {
+            let height = w.text_height();
+            let last = w.lines.len() - 1;
+            // keep the cursor on the same screen row while the view moves by a page
+            w.top_visible = (w.top_visible + height)
+                .min(w.lines.len().saturating_sub(height))
+                .max(w.top_visible);
+            let row = min(pos.0 + height, last);
+            let col = w.same_column(row);
+            w.move_to(TextPos(row, col));
+        } // go one screen down
+        KeyCode::PageUp => {
+            let height = w.text_height();
+            w.top_visible = w.top_visible.saturating_sub(height);
+            let row = pos.0.saturating_sub(height);
+            let col = w.same_column(row);
+            w.move_to(TextPos(row, col));
+        } // go one screen up
+        KeyCode::Home if ctrl => w.move_to(TextPos(0, 0)), // go to the start of the buffer
+        KeyCode::End if ctrl => {
+            let last = w.lines.len() - 1;
+            w.move_to(TextPos(last, w.lines[last].len()));
+        } // go to the end of the buffer
+        KeyCode::Home => {
+            let indent = w.lines[pos.0].indent_len();
+            w.move_to(TextPos(pos.0, if pos.1 == indent { 0 } else { indent }));
+        } // go to the first non-blank character, or to the begin of line if already there
+        KeyCode::End => {
+            w.location.1 = w.lines[w.location.0].len();
+            w.cursor_location.1 = w.lines[w.location.0].char_len;
+        } // go to end of line
+        KeyCode::Tab => {
+            let width = tab_size - w.display_col() % tab_size;
+            let text = if config.use_tabs { tab } else { " ".repeat(width) };
+            w.lines[pos.0].insert_str(pos.1, &text);
+            w.location.1 += text.len();
+            w.cursor_location.1 += text.len();
+            w.f_status = FileStatus::Edited;
+        } // insert a tab or spaces up to the next tab stop
+        KeyCode::BackTab => {
+            let chars = w.lines[pos.0].back_tab(tab_size);
+            if w.location.1 > chars {
+                w.location.1 -= chars;
+            } else {
+                w.location.1 = 0;
+            }
+            if w.cursor_location.1 > chars {
+                w.cursor_location.1 -= chars;
+            } else {
+                w.cursor_location.1 = 0;
+            }
+            if chars > 0 {
+                w.f_status = FileStatus::Edited;
+            }
+        } // delete tab from the start of the current line
+        KeyCode::Delete if ctrl && pos.1 < w.lines[pos.0].len() => {
+            let end = w.lines[pos.0].next_word_boundary(pos.1, &config.word_chars);
+            w.lines[pos.0].remove_range(pos.1..end);
+            w.f_status = FileStatus::Edited;
+        } // delete to the end of the word
+        KeyCode::Delete => {
+            if pos.1 == w.lines[pos.0].len() && pos.0 < w.lines.len() - 1 {
+                let data = w.lines[pos.0 + 1].data.to_owned();
+                w.lines[pos.0].push_str(&data);
+                w.lines.remove(pos.0 + 1);
+                w.f_status = FileStatus::Edited;
+            } else if pos.1 != w.lines[pos.0].len() {
+                w.lines[pos.0].remove(pos.1);
+                w.f_status = FileStatus::Edited;
+            }
+        } // delete next character if at end of line merge the next one into current
+        KeyCode::Char(c) if next_char == Some(c) && config.auto_pairs.iter().any(|p| p.1 == c) => {
+            w.location.1 = next_char_bound;
+            w.cursor_location.1 += 1;
+        } // type over the closing character
+        KeyCode::Char(c) => {
+            w.lines[pos.0].insert(pos.1, c);
+            if let Some(&(open, close)) = config.auto_pairs.iter().find(|p| p.0 == c) {
+                // pair only before blanks or closers, a quote also not right after a word
+                let free = next_char.is_none_or(|n| {
+                    n.is_whitespace() || config.auto_pairs.iter().any(|p| p.1 == n)
+                });
+                let quote =
+                    open == close && prev_char.is_some_and(|p| p.is_alphanumeric() || p == c);
+                if free && !quote {
+                    w.lines[pos.0].insert(pos.1 + c.len_utf8(), close);
+                }
+            }
+            w.location.1 += c.len_utf8();
+            w.cursor_location.1 += 1;
+            w.f_status = FileStatus::Edited;
+        } // insert character and advance the character pointer by 1
+        // KeyCode::Null => todo!(),
+        // KeyCode::Esc => todo!(),
+        _ => {}
+    }
+}
+
+/// move the head of the block by one line or column, a new block starts at the cursor,
+/// `false` for keys that do not move it
+fn extend_block(code: KeyCode, w: &mut FileData) -> bool {
+    let start = TextPos(w.location.0, w.cursor_location.1);
+    let mut b = w.block.unwrap_or(Block {
+        anchor: start,
+        head: start,
+    });
+    match code {
+        KeyCode::Left => b.head.1 = b.head.1.saturating_sub(1),
+        KeyCode::Right => b.head.1 += 1,
+        KeyCode::Up => b.head.0 = b.head.0.saturating_sub(1),
+        KeyCode::Down => b.head.0 = min(b.head.0 + 1, w.lines.len() - 1),
+        _ => return false,
+    }
+    w.cursors.clear();
+    w.anchor = None;
+    w.history.typing = false;
+    w.block = Some(b);
+    let pos = text_pos(w, b.head);
+    w.move_to(pos);
+    scroll(w);
+    true
+}
+
+/// run an edit on the selected lines or the cursor line as one undo step
+fn line_edit(w: &mut FileData, edit: impl FnOnce(&mut FileData, RangeInclusive<usize>)) {
+    buffer_edit(w, |w| {
+        w.cursors.clear();
+        let rows = w.target_rows();
+        edit(w, rows);
+    });
+}
+
+/// run an edit of a shortcut as one undo step, refused in a read-only buffer
+fn buffer_edit(w: &mut FileData, edit: impl FnOnce(&mut FileData)) {
+    if w.read_only {
+        w.message =
+            Message::with_timeout("Buffer is read-only".to_owned(), Duration::from_secs(5));
+        return;
+    }
+    w.checkpoint();
+    w.history.typing = false;
+    edit(w);
+    w.drop_unchanged_checkpoint();
+    scroll(w);
+}
+
+/// one level of indentation, a tab with `use_tabs`
+fn indent_unit(config: &Config) -> String {
+    match config.use_tabs {
+        true => "\t".to_owned(),
+        false => " ".repeat(config.tab_size),
+    }
+}
+
+/// indent or outdent every line in `rows` by one level, the cursor and the selection
+/// stay on the same text
+fn indent_rows(w: &mut FileData, rows: RangeInclusive<usize>, indent: bool, config: &Config) {
+    let tab = indent_unit(config);
+    let mut location = w.location;
+    let mut anchor = w.anchor;
+    for row in rows {
+        let line = &mut w.lines[row];
+        // bytes added to the start of the line, negative when removed
+        let delta = if indent {
+            if line.is_empty() {
+                continue;
+            }
+            line.insert_str(0, &tab);
+            tab.len() as isize
+        } else {
+            -(line.back_tab(config.tab_size.max(1)) as isize)
+        };
+        for p in std::iter::once(&mut location).chain(anchor.as_mut()) {
+            if p.0 == row {
+                p.1 = p.1.saturating_add_signed(delta);
+            }
+        }
+        if delta != 0 {
+            w.f_status = FileStatus::Edited;
+        }
+    }
+    w.anchor = anchor;
+    w.move_to(location);
+}
+/// Edit the text of a prompt: readline style keys, Up and Down pick from its list or
+/// recall earlier entries, Enter confirms and Esc cancels.
+pub fn prompt_input(prompt: &mut Prompt, ke: KeyEvent, width: usize) {
+    let (prev_char_bound, next_char_bound) = prompt.data.get_next_and_prev_chars(prompt.location);
+    let pos = prompt.location;
+    let len = prompt.data.len();
+    let typed = prompt.data.data.clone();
+    let ctrl = ke.modifiers.contains(KeyModifiers::CONTROL);
+    let alt = ke.modifiers.contains(KeyModifiers::ALT);
+    // typing replaces a filled in text, any other key keeps it
+    if std::mem::take(&mut prompt.prefilled) && matches!(ke.code, KeyCode::Char(_)) && !ctrl {
+        prompt.set_text("");
+        return prompt_input(prompt, ke, width);
+    }
+    let path_prompt = matches!(
+        prompt.p_type,
+        PromptType::Save | PromptType::Open | PromptType::NewFile | PromptType::Rename
+    );
+    if prompt.p_type == PromptType::Confirm {
+        match ke.code {
+            KeyCode::Char('y' | 'Y') => {
+                prompt.set_text("y");
+                prompt.status = PromptStatus::Success;
+            }
+            KeyCode::Char('n' | 'N') | KeyCode::Esc => prompt.status = PromptStatus::Cancelled,
+            _ => {}
+        }
+        return;
+    }
+    let word_start = prompt.data.prev_word_boundary(pos, "_");
+    let word_end = prompt.data.next_word_boundary(pos, "_");
+    match ke.code {
+        KeyCode::Up | KeyCode::Down => match prompt.list {
+            Some(ref mut list) => list.select(if ke.code == KeyCode::Up { -1 } else { 1 }),
+            None => prompt.recall(ke.code == KeyCode::Up),
+        },
+        KeyCode::Tab if path_prompt => complete_path(prompt),
+        // Esc closes the list of entries first
+        KeyCode::Esc if path_prompt && prompt.list.is_some() => prompt.list = None,
+        KeyCode::Tab if prompt.p_type == PromptType::CommandLine => {
+            if let Some(line) = ex::complete(&prompt.data.data) {
+                prompt.set_text(&line);
+            }
+        }
+        // Ctrl+Backspace, legacy terminals send Ctrl+H
+        KeyCode::Backspace | KeyCode::Char('h') if ctrl => prompt.remove_range(word_start..pos),
+        KeyCode::Backspace => {
+            if pos > 0 {
+                prompt.data.remove(prev_char_bound);
+                prompt.location = prev_char_bound;
+                prompt.cursor -= 1;
+            }
+        }
+        KeyCode::Enter => {
+            prompt.status = PromptStatus::Success;
+        }
+        KeyCode::Left if ctrl => prompt.move_to(word_start),
+        KeyCode::Char('b') if alt => prompt.move_to(word_start),
+        KeyCode::Right if ctrl => prompt.move_to(word_end),
+        KeyCode::Char('f') if alt => prompt.move_to(word_end),
+        KeyCode::Left => {
+            if pos > 0 {
+                prompt.location = prev_char_bound;
+                prompt.cursor -= 1;
+            }
+        }
+        KeyCode::Right => {
+            if pos < prompt.data.len() {
+                prompt.location = next_char_bound;
+                prompt.cursor += 1;
+            }
+        }
+        KeyCode::Home => prompt.move_to(0),
+        KeyCode::Char('a') if ctrl => prompt.move_to(0),
+        KeyCode::End => prompt.move_to(len),
+        KeyCode::Char('e') if ctrl => prompt.move_to(len),
+        KeyCode::Delete if ctrl => prompt.remove_range(pos..word_end),
+        KeyCode::Delete => {
+            if pos != prompt.data.len() {
+                prompt.data.remove(pos);
+            }
+        }
+        // Ctrl+U / Ctrl+K : delete to the start or the end
+        KeyCode::Char('u') if ctrl => prompt.remove_range(0..pos),
+        KeyCode::Char('k') if ctrl => prompt.remove_range(pos..len),
+        // Ctrl+W : delete back to the previous whitespace
+        KeyCode::Char('w') if ctrl => {
+            let text = &prompt.data.data[..pos];
+            let start = text.trim_end().rfind(char::is_whitespace).map_or(0, |i| i + 1);
+            prompt.remove_range(start..pos);
+        }
+        KeyCode::Char(_) if ctrl || alt => {}
+        KeyCode::Char(c) => {
+            prompt.data.insert(pos, c);
+            prompt.location += c.len_utf8();
+            prompt.cursor += 1;
+        }
+        KeyCode::Esc => prompt.status = PromptStatus::Cancelled,
+        _ => {}
+    }
+    if prompt.data.data != typed {
+        if path_prompt {
+            // the open list follows what is typed
+            if prompt.list.is_some() {
+                prompt.list = path_list(prompt);
+            }
+        } else if let Some(ref mut list) = prompt.list {
+            list.filter(&prompt.data.data);
+        }
+    }
+    prompt_scroll(prompt, width);
+}
+
+/// Tab in a path prompt: take the entry picked from the list, or extend the typed name as
+/// far as the entries agree and list them when that does not tell them apart
+fn complete_path(prompt: &mut Prompt) {
+    let text = prompt.data.data.clone();
+    let (dir, _) = paths::split(&text);
+    if let Some(list) = prompt.list.take() {
+        if let Some(i) = list.selected_item() {
+            prompt.set_text(&format!("{}{}", dir, list.items[i].label));
+        }
+        return;
+    }
+    if let Some(done) = paths::complete(&prompt.dir, &text) {
+        prompt.set_text(&done);
+    }
+    prompt.list = path_list(prompt).filter(|l| l.items.len() > 1);
+}
+
+/// the directory entries matching the typed path, `None` when there are none
+fn path_list(prompt: &Prompt) -> Option<List> {
+    let items: Vec<ListItem> = paths::candidates(&prompt.dir, &prompt.data.data)
+        .into_iter()
+        .map(|label| ListItem {
+            label,
+            detail: String::new(),
+        })
+        .collect();
+    (!items.is_empty()).then(|| List::new(items))
+}
+
+/// save the buffer to its path and report the outcome in the message line
+pub fn save_file(w: &mut FileData) {
+    match w.save() {
+        Ok(()) => {
+            w.message = Message::with_timeout(
+                format!("Saved: {}", w.path.display()),
+                Duration::from_secs(5),
+            );
+            w.f_status = FileStatus::Clean;
+        }
+        Err(e) => w.message = Message::with_timeout(e.to_string(), Duration::from_secs(5)),
+    }
+}
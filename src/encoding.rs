@@ -0,0 +1,148 @@
+use encoding_rs::{Encoding, UTF_16BE, UTF_16LE, UTF_8};
+
+/// how many bytes from the start of the file are inspected when guessing the encoding
+const SAMPLE_SIZE: usize = 8192;
+
+/// How the text of a file is stored on disk.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FileEncoding {
+    pub encoding: &'static Encoding,
+    /// the file started with a byte order mark, it is written back on save
+    pub bom: bool,
+}
+impl Default for FileEncoding {
+    fn default() -> Self {
+        Self {
+            encoding: UTF_8,
+            bom: false,
+        }
+    }
+}
+impl FileEncoding {
+    pub fn name(&self) -> String {
+        if self.bom {
+            format!("{} BOM", self.encoding.name())
+        } else {
+            self.encoding.name().to_owned()
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum Decoded {
+    Text(String, FileEncoding),
+    /// the contents do not look like text, decoded with the fallback encoding for viewing
+    Binary(String, FileEncoding),
+}
+
+/// Guess the encoding of `bytes`: a byte order mark wins, then UTF-16 without a mark,
+/// then UTF-8, and everything else is read with the `fallback` legacy encoding.
+pub fn detect(bytes: &[u8], fallback: &'static Encoding) -> Decoded {
+    if let Some((encoding, bom_len)) = Encoding::for_bom(bytes) {
+        let (text, _) = encoding.decode_without_bom_handling(&bytes[bom_len..]);
+        return Decoded::Text(
+            text.into_owned(),
+            FileEncoding {
+                encoding,
+                bom: true,
+            },
+        );
+    }
+    if let Some(encoding) = guess_utf16(bytes) {
+        let (text, enc) = decode_as(bytes, encoding);
+        return Decoded::Text(text, enc);
+    }
+    if looks_binary(bytes) {
+        let (text, _) = fallback.decode_without_bom_handling(bytes);
+        return Decoded::Binary(
+            text.into_owned(),
+            FileEncoding {
+                encoding: fallback,
+                bom: false,
+            },
+        );
+    }
+    match std::str::from_utf8(bytes) {
+        Ok(text) => Decoded::Text(text.to_owned(), FileEncoding::default()),
+        Err(_) => {
+            let (text, enc) = decode_as(bytes, fallback);
+            Decoded::Text(text, enc)
+        }
+    }
+}
+
+/// Decode with an encoding chosen by the user, a matching byte order mark is skipped.
+pub fn decode_as(bytes: &[u8], encoding: &'static Encoding) -> (String, FileEncoding) {
+    let (bom, bytes) = match Encoding::for_bom(bytes) {
+        Some((e, len)) if e == encoding => (true, &bytes[len..]),
+        _ => (false, bytes),
+    };
+    let (text, _) = encoding.decode_without_bom_handling(bytes);
+    (text.into_owned(), FileEncoding { encoding, bom })
+}
+
+/// Encode `text` for saving, fails with the first character the encoding can't represent.
+pub fn encode(text: &str, enc: FileEncoding) -> Result<Vec<u8>, char> {
+    let mut out = Vec::with_capacity(text.len() + 3);
+    if enc.encoding == UTF_16LE || enc.encoding == UTF_16BE {
+        // encoding_rs only decodes UTF-16, encoding is done by hand
+        let le = enc.encoding == UTF_16LE;
+        let unit = |u: u16| if le { u.to_le_bytes() } else { u.to_be_bytes() };
+        if enc.bom {
+            out.extend(unit(0xFEFF));
+        }
+        for u in text.encode_utf16() {
+            out.extend(unit(u));
+        }
+        return Ok(out);
+    }
+    if enc.encoding == UTF_8 {
+        if enc.bom {
+            out.extend([0xEF, 0xBB, 0xBF]);
+        }
+        out.extend(text.as_bytes());
+        return Ok(out);
+    }
+    let (bytes, _, had_errors) = enc.encoding.encode(text);
+    if had_errors {
+        // find the offending character, encoding_rs replaced it with a numeric character reference
+        let bad = text
+            .chars()
+            .find(|c| enc.encoding.encode(&c.to_string()).2)
+            .unwrap_or(char::REPLACEMENT_CHARACTER);
+        return Err(bad);
+    }
+    out.extend(bytes.iter());
+    Ok(out)
+}
+
+/// UTF-16 without a byte order mark shows up as every other byte being zero for ASCII text
+fn guess_utf16(bytes: &[u8]) -> Option<&'static Encoding> {
+    let sample = &bytes[..bytes.len().min(SAMPLE_SIZE)];
+    if sample.len() < 2 || !sample.len().is_multiple_of(2) {
+        return None;
+    }
+    let pairs = sample.len() / 2;
+    let even = sample.iter().step_by(2).filter(|b| **b == 0).count();
+    let odd = sample.iter().skip(1).step_by(2).filter(|b| **b == 0).count();
+    if odd * 10 >= pairs * 4 && even * 10 < pairs {
+        Some(UTF_16LE)
+    } else if even * 10 >= pairs * 4 && odd * 10 < pairs {
+        Some(UTF_16BE)
+    } else {
+        None
+    }
+}
+
+/// NUL bytes or a lot of control characters mean the file is not text
+fn looks_binary(bytes: &[u8]) -> bool {
+    let sample = &bytes[..bytes.len().min(SAMPLE_SIZE)];
+    if sample.contains(&0) {
+        return true;
+    }
+    let control = sample
+        .iter()
+        .filter(|b| b.is_ascii_control() && !matches!(b, b'\t' | b'\n' | b'\r' | 0x0C | 0x1B))
+        .count();
+    control * 10 > sample.len()
+}
@@ -0,0 +1,121 @@
+use std::{fmt, io, path::PathBuf};
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Debug)]
+pub enum Error {
+    /// reading input or drawing to the terminal failed
+    Terminal(io::Error),
+    /// the config file exists but could not be read
+    ConfigRead { path: PathBuf, source: io::Error },
+    /// `--generate-config` could not create the config file or its folder
+    ConfigWrite { path: PathBuf, source: io::Error },
+    /// the config file is not valid, `line` and `column` start at 1
+    ConfigParse {
+        path: PathBuf,
+        line: usize,
+        column: usize,
+        message: String,
+    },
+    Open { path: PathBuf, source: io::Error },
+    Save { path: PathBuf, source: io::Error },
+    /// the buffer contains a character the file encoding can't store
+    Unencodable {
+        path: PathBuf,
+        c: char,
+        encoding: &'static str,
+    },
+    UnknownEncoding(String),
+    ReadOnly(PathBuf),
+    /// a command line command that can't run, the text says why
+    Command(String),
+    /// a task panicked, the files written by the panic hook
+    Crashed {
+        report: Option<PathBuf>,
+        rescue: Option<PathBuf>,
+    },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Terminal(e) => write!(f, "terminal error: {}", e),
+            Error::ConfigRead { path, source } => {
+                write!(f, "can't read config {}: {}", path.display(), source)
+            }
+            Error::ConfigWrite { path, source } => {
+                write!(f, "can't write config {}: {}", path.display(), source)
+            }
+            Error::ConfigParse {
+                path,
+                line,
+                column,
+                message,
+            } => write!(
+                f,
+                "invalid config {}:{}:{}: {}",
+                path.display(),
+                line,
+                column,
+                message.trim_end()
+            ),
+            Error::Open { path, source } => write!(f, "can't open {}: {}", path.display(), source),
+            Error::Save { path, source } => write!(f, "can't save {}: {}", path.display(), source),
+            Error::Unencodable { path, c, encoding } => write!(
+                f,
+                "can't save {}: '{}' has no representation in {}",
+                path.display(),
+                c,
+                encoding
+            ),
+            Error::UnknownEncoding(label) => write!(f, "unknown encoding: \"{}\"", label),
+            Error::ReadOnly(path) => write!(f, "{} is read-only", path.display()),
+            Error::Command(message) => write!(f, "{}", message),
+            Error::Crashed { report, rescue } => {
+                write!(f, "te crashed")?;
+                if let Some(rescue) = rescue {
+                    write!(f, ", unsaved changes were written to {}", rescue.display())?;
+                }
+                if let Some(report) = report {
+                    write!(f, ", crash report: {}", report.display())?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Terminal(e)
+            | Error::ConfigRead { source: e, .. }
+            | Error::ConfigWrite { source: e, .. }
+            | Error::Open { source: e, .. }
+            | Error::Save { source: e, .. } => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// for the `?` on crossterm calls, other io errors say what they were doing
+impl From<io::Error> for Error {
+    fn from(value: io::Error) -> Self {
+        Error::Terminal(value)
+    }
+}
+
+impl Error {
+    /// config error pointing at byte `offset` of the config `text`
+    pub fn config_parse(path: PathBuf, text: &str, offset: usize, message: &str) -> Self {
+        let before = &text[..text.floor_char_boundary(offset.min(text.len()))];
+        let line = before.matches('\n').count() + 1;
+        let column = before.rsplit('\n').next().unwrap_or("").chars().count() + 1;
+        Error::ConfigParse {
+            path,
+            line,
+            column,
+            message: message.to_owned(),
+        }
+    }
+}
@@ -0,0 +1,139 @@
+//! The command line: `:`-style commands with arguments, like `w path` or `s/foo/bar/g`.
+
+use std::path::{Path, PathBuf};
+
+use regex::Regex;
+
+use crate::{
+    error::{Error, Result},
+    paths::{self, expand_home},
+};
+
+#[derive(Clone, Debug)]
+pub enum ExCommand {
+    /// save, to another path when one is given
+    Write(Option<PathBuf>),
+    /// open another file in place of the buffer
+    Edit(PathBuf),
+    /// `set name=value`, without a value the current one is shown
+    Set(String, Option<String>),
+    /// move to a line, numbered like the gutter
+    Goto(usize),
+    /// replace matches of `pattern` on the selected lines or the cursor line,
+    /// every match with the `g` flag and only the first one otherwise
+    Substitute {
+        pattern: Regex,
+        replacement: String,
+        global: bool,
+    },
+    /// sort the selected lines or the whole buffer
+    Sort,
+    /// run a shell command, the selected lines are piped through it
+    Shell(String),
+    /// list the lines of the project's files matching the pattern
+    Grep(Regex),
+    /// list the matches with their replacement, to apply to every file at once
+    Replace { pattern: Regex, replacement: String },
+}
+
+/// names completed with Tab, the short forms are accepted too
+pub const NAMES: &[&str] = &["edit", "goto", "grep", "replace", "set", "sort", "write"];
+
+pub fn parse(line: &str) -> Result<ExCommand> {
+    let line = line.trim();
+    if let Some(cmd) = line.strip_prefix('!') {
+        return match cmd.trim() {
+            "" => Err(Error::Command("! needs a shell command".to_owned())),
+            cmd => Ok(ExCommand::Shell(cmd.to_owned())),
+        };
+    }
+    if let Ok(n) = line.parse() {
+        return Ok(ExCommand::Goto(n));
+    }
+    if let Some(rest) = line.strip_prefix('s') {
+        if rest.starts_with(|c: char| !c.is_alphanumeric() && !c.is_whitespace()) {
+            let (pattern, replacement, global) = substitution(rest)?;
+            return Ok(ExCommand::Substitute {
+                pattern,
+                replacement,
+                global,
+            });
+        }
+    }
+    let (name, arg) = match line.split_once(char::is_whitespace) {
+        Some((name, arg)) => (name, Some(arg.trim())),
+        None => (line, None),
+    };
+    let path = |arg: Option<&str>| arg.map(expand_home);
+    match (name, arg) {
+        ("w" | "write", arg) => Ok(ExCommand::Write(path(arg))),
+        ("e" | "edit", Some(arg)) => Ok(ExCommand::Edit(expand_home(arg))),
+        ("set", Some(arg)) => Ok(match arg.split_once('=') {
+            Some((name, value)) => ExCommand::Set(name.trim().to_owned(), Some(value.to_owned())),
+            None => ExCommand::Set(arg.to_owned(), None),
+        }),
+        ("goto", Some(arg)) => arg
+            .parse()
+            .map(ExCommand::Goto)
+            .map_err(|_| Error::Command(format!("not a line number: {}", arg))),
+        ("sort", None) => Ok(ExCommand::Sort),
+        ("grep", Some(arg)) => Regex::new(arg)
+            .map(ExCommand::Grep)
+            .map_err(|e| Error::Command(e.to_string())),
+        ("replace", Some(arg)) => {
+            // every match is replaced, a `g` flag changes nothing
+            let (pattern, replacement, _) = substitution(arg)?;
+            Ok(ExCommand::Replace {
+                pattern,
+                replacement,
+            })
+        }
+        ("e" | "edit" | "set" | "goto" | "grep" | "replace", None) => {
+            Err(Error::Command(format!("{} needs an argument", name)))
+        }
+        _ => Err(Error::Command(format!("unknown command: {}", name))),
+    }
+}
+
+/// `/pattern/replacement/flags` after the `s`, the first character is the delimiter.
+/// Returns the pattern, the replacement and whether the `g` flag was given.
+fn substitution(text: &str) -> Result<(Regex, String, bool)> {
+    let mut chars = text.chars();
+    let delim = chars.next().unwrap_or('/');
+    let parts: Vec<&str> = chars.as_str().splitn(3, delim).collect();
+    let (pattern, replacement, flags) = match parts[..] {
+        [p, r, f] => (p, r, f),
+        [p, r] => (p, r, ""),
+        _ => {
+            return Err(Error::Command(format!(
+                "use s{d}pattern{d}replacement{d}",
+                d = delim
+            )))
+        }
+    };
+    if let Some(f) = flags.chars().find(|f| *f != 'g') {
+        return Err(Error::Command(format!("unknown flag: {}", f)));
+    }
+    Ok((
+        Regex::new(pattern).map_err(|e| Error::Command(e.to_string()))?,
+        replacement.to_owned(),
+        flags.contains('g'),
+    ))
+}
+
+/// What Tab turns the command line into: the command name, or the path argument of
+/// `w` and `e`, extended as far as all the candidates agree. `None` when nothing fits.
+pub fn complete(line: &str) -> Option<String> {
+    let Some((name, arg)) = line.split_once(' ') else {
+        let names = NAMES.iter().filter(|n| n.starts_with(line)).map(|n| n.to_string());
+        let done = paths::common_prefix(names)?;
+        // a single match is complete, the argument comes next
+        let unique = NAMES.iter().filter(|n| n.starts_with(&done)).count() == 1;
+        return Some(if unique { done + " " } else { done });
+    };
+    if !matches!(name, "w" | "write" | "e" | "edit") {
+        return None;
+    }
+    let arg = arg.trim_start();
+    Some(format!("{} {}", name, paths::complete(Path::new("."), arg)?))
+}
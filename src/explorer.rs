@@ -0,0 +1,177 @@
+//! The file explorer: a tree of the working directory in a pane left of the text, folders
+//! expand in place and entries ignored by git are hidden unless asked for.
+
+use std::{
+    collections::BTreeSet,
+    path::{Path, PathBuf},
+};
+
+use ignore::WalkBuilder;
+
+/// columns taken by the pane, the last one separates it from the text
+pub const EXPLORER_WIDTH: usize = 24;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Entry {
+    pub path: PathBuf,
+    /// folders above it up to the root
+    pub depth: usize,
+    pub is_dir: bool,
+}
+impl Entry {
+    pub fn name(&self) -> String {
+        self.path
+            .file_name()
+            .map_or_else(String::new, |n| n.to_string_lossy().into_owned())
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Explorer {
+    pub root: PathBuf,
+    /// the rows of the tree, children follow their expanded folder
+    pub entries: Vec<Entry>,
+    /// index into `entries`
+    pub selected: usize,
+    /// first entry on the screen
+    pub top: usize,
+    expanded: BTreeSet<PathBuf>,
+    /// list entries `.gitignore` excludes too
+    pub show_ignored: bool,
+    pub visible: bool,
+    /// keys go to the tree instead of the text
+    pub focused: bool,
+}
+impl Explorer {
+    /// a shown and focused tree of `root` with its folders collapsed
+    pub fn new(root: PathBuf) -> Self {
+        let mut e = Self {
+            root,
+            entries: Vec::new(),
+            selected: 0,
+            top: 0,
+            expanded: BTreeSet::new(),
+            show_ignored: false,
+            visible: true,
+            focused: true,
+        };
+        e.refresh();
+        e
+    }
+    /// read the folders again, the selection stays on the same path when it still exists
+    pub fn refresh(&mut self) {
+        let selected = self.selected_entry().map(|e| e.path.clone());
+        let mut entries = Vec::new();
+        self.read_dir(&self.root, 0, &mut entries);
+        self.entries = entries;
+        match selected {
+            Some(path) => self.select_path(&path),
+            None => self.selected = 0,
+        }
+    }
+    fn read_dir(&self, dir: &Path, depth: usize, entries: &mut Vec<Entry>) {
+        let ignored = !self.show_ignored;
+        let walk = WalkBuilder::new(dir)
+            .max_depth(Some(1))
+            .hidden(false)
+            .ignore(false)
+            .git_ignore(ignored)
+            .git_exclude(ignored)
+            .git_global(ignored)
+            .require_git(false)
+            .filter_entry(|e| e.file_name() != ".git")
+            .build();
+        let mut children: Vec<Entry> = walk
+            .flatten()
+            .filter(|e| e.depth() == 1)
+            .map(|e| {
+                let is_dir = e.path().is_dir();
+                // entries of `.` are named like the rest of the editor names files
+                let path = e.into_path();
+                let path = path.strip_prefix(".").map_or(path.clone(), Path::to_path_buf);
+                Entry {
+                    path,
+                    depth,
+                    is_dir,
+                }
+            })
+            .collect();
+        // folders first, then by name
+        children.sort_by_key(|e| (!e.is_dir, e.name()));
+        for child in children {
+            let expanded = child.is_dir && self.expanded.contains(&child.path);
+            let path = child.path.clone();
+            entries.push(child);
+            if expanded {
+                self.read_dir(&path, depth + 1, entries);
+            }
+        }
+    }
+    pub fn selected_entry(&self) -> Option<&Entry> {
+        self.entries.get(self.selected)
+    }
+    /// move the selection by `by` entries, stopping at either end
+    pub fn select(&mut self, by: isize) {
+        let last = self.entries.len().saturating_sub(1);
+        self.selected = self.selected.saturating_add_signed(by).min(last);
+    }
+    /// select the entry of `path`, or the closest folder above it that is shown
+    pub fn select_path(&mut self, path: &Path) {
+        let found = path
+            .ancestors()
+            .find_map(|p| self.entries.iter().position(|e| e.path == p));
+        self.selected = found.unwrap_or(0).min(self.entries.len().saturating_sub(1));
+    }
+    /// open or close the selected folder, false for a file
+    pub fn toggle(&mut self) -> bool {
+        let Some(e) = self.selected_entry().filter(|e| e.is_dir) else {
+            return false;
+        };
+        let path = e.path.clone();
+        if !self.expanded.remove(&path) {
+            self.expanded.insert(path);
+        }
+        self.refresh();
+        true
+    }
+    pub fn is_expanded(&self, path: &Path) -> bool {
+        self.expanded.contains(path)
+    }
+    pub fn expand(&mut self) {
+        if let Some(path) = self.selected_entry().filter(|e| e.is_dir).map(|e| e.path.clone()) {
+            if self.expanded.insert(path) {
+                self.refresh();
+            }
+        }
+    }
+    /// close the selected folder, or go to the folder holding the selected entry
+    pub fn collapse(&mut self) {
+        let Some(e) = self.selected_entry().cloned() else {
+            return;
+        };
+        if e.is_dir && self.expanded.remove(&e.path) {
+            self.refresh();
+        } else if let Some(parent) = e.path.parent().filter(|_| e.depth > 0) {
+            self.select_path(parent);
+        }
+    }
+    /// the folder new files go into: the selected one, or the one holding the selected file
+    pub fn target_dir(&self) -> PathBuf {
+        match self.selected_entry() {
+            Some(e) if e.is_dir => e.path.clone(),
+            Some(e) => match e.path.parent() {
+                Some(dir) if e.depth > 0 => dir.to_path_buf(),
+                _ => self.root.clone(),
+            },
+            None => self.root.clone(),
+        }
+    }
+    /// keep the selection within the `height` rows of the pane
+    pub fn scroll(&mut self, height: usize) {
+        if self.selected < self.top {
+            self.top = self.selected;
+        } else if height > 0 && self.selected >= self.top + height {
+            self.top = self.selected + 1 - height;
+        }
+    }
+}
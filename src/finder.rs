@@ -0,0 +1,152 @@
+//! Files of the project for the Ctrl+P picker, listed on a tokio task so a large tree does
+//! not hold up the keys typed meanwhile.
+
+use std::{
+    fs,
+    io::Read,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use ignore::WalkBuilder;
+use parking_lot::Mutex;
+
+/// files handed over to the picker at once
+const BATCH: usize = 256;
+/// bytes of a file read for its preview
+const PREVIEW_BYTES: u64 = 64 * 1024;
+
+/// The folder holding `.git` above `start`, or `start` itself outside of a repository.
+/// Relative to the working directory when it is inside it.
+pub fn project_root(start: &Path) -> PathBuf {
+    let cwd = std::env::current_dir().unwrap_or_default();
+    let start = cwd.join(start);
+    let root = start
+        .ancestors()
+        .find(|dir| dir.join(".git").exists())
+        .unwrap_or(&start);
+    match root.strip_prefix(&cwd) {
+        Ok(rel) => rel.to_path_buf(),
+        Err(_) => root.to_path_buf(),
+    }
+}
+
+#[derive(Debug)]
+struct Found<T> {
+    items: Vec<T>,
+    done: bool,
+}
+
+/// What a walk on a blocking tokio task found so far, taken by the editor as it comes.
+#[derive(Clone, Debug)]
+pub struct Feed<T> {
+    found: Arc<Mutex<Found<T>>>,
+    /// items handed out by `take_new` so far
+    taken: usize,
+}
+impl<T: Clone + Send + 'static> Feed<T> {
+    /// Run `work` on a blocking tokio task when there is a runtime, otherwise it is done
+    /// on return. It hands what it finds to the sink, the feed is done when it returns.
+    pub fn start(work: impl FnOnce(&Sink<T>) + Send + 'static) -> Self {
+        let found = Arc::new(Mutex::new(Found {
+            items: Vec::new(),
+            done: false,
+        }));
+        let sink = Sink(found.clone());
+        match tokio::runtime::Handle::try_current() {
+            Ok(rt) => {
+                rt.spawn_blocking(move || work(&sink));
+            }
+            Err(_) => work(&sink),
+        }
+        Self { found, taken: 0 }
+    }
+    /// the work is over and every item was taken
+    pub fn done(&self) -> bool {
+        let found = self.found.lock();
+        found.done && self.taken == found.items.len()
+    }
+    /// the items found since the last call
+    pub fn take_new(&mut self) -> Vec<T> {
+        let found = self.found.lock();
+        let new = found.items[self.taken..].to_vec();
+        self.taken = found.items.len();
+        new
+    }
+}
+
+/// The end of a `Feed` the work puts its items into, dropping it ends the feed.
+pub struct Sink<T>(Arc<Mutex<Found<T>>>);
+impl<T> Sink<T> {
+    /// hand over `items`, false once nobody takes them any more
+    pub fn send(&self, items: &mut Vec<T>) -> bool {
+        if Arc::strong_count(&self.0) == 1 {
+            return false;
+        }
+        self.0.lock().items.append(items);
+        true
+    }
+}
+impl<T> Drop for Sink<T> {
+    fn drop(&mut self) {
+        self.0.lock().done = true;
+    }
+}
+
+/// the files under a folder, paths relative to it
+pub type Index = Feed<String>;
+
+/// Start listing the files under `root` that `.gitignore` does not exclude.
+pub fn index(root: PathBuf) -> Index {
+    Feed::start(move |sink| {
+        let mut batch = Vec::new();
+        for (_, rel) in files(&root) {
+            batch.push(rel);
+            // the picker was closed, nobody is waiting for the rest
+            if batch.len() == BATCH && !sink.send(&mut batch) {
+                return;
+            }
+        }
+        sink.send(&mut batch);
+    })
+}
+
+/// The files under `root` that `.gitignore` does not exclude, with their paths relative
+/// to it. An empty root is the working directory.
+pub fn files(root: &Path) -> impl Iterator<Item = (PathBuf, String)> {
+    let dir = if root.as_os_str().is_empty() {
+        PathBuf::from(".")
+    } else {
+        root.to_path_buf()
+    };
+    // dotfiles are part of the project too, like in the explorer
+    WalkBuilder::new(&dir)
+        .hidden(false)
+        .require_git(false)
+        .filter_entry(|e| e.file_name() != ".git")
+        .build()
+        .flatten()
+        .filter(|e| e.file_type().is_some_and(|t| t.is_file()))
+        .filter_map(move |e| {
+            let rel = e.path().strip_prefix(&dir).ok()?.to_string_lossy().into_owned();
+            Some((e.into_path(), rel))
+        })
+}
+
+/// The first `rows` lines of the file at `path` for a preview, tabs as spaces. A file that
+/// can't be read or looks binary gives a line saying so.
+pub fn preview(path: &Path, rows: usize) -> Vec<String> {
+    let mut bytes = Vec::new();
+    let read = fs::File::open(path).and_then(|f| f.take(PREVIEW_BYTES).read_to_end(&mut bytes));
+    if let Err(e) = read {
+        return vec![format!("can't read {}: {}", path.display(), e)];
+    }
+    if bytes.contains(&0) {
+        return vec!["binary file".to_owned()];
+    }
+    String::from_utf8_lossy(&bytes)
+        .lines()
+        .take(rows)
+        .map(|l| l.replace('\t', "    "))
+        .collect()
+}
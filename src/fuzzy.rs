@@ -0,0 +1,61 @@
+//! Fuzzy matching of typed text against names, as used by the pickers.
+
+/// How well `pattern` matches `candidate` when its characters appear in order, ignoring
+/// case. Higher is better, runs of characters and matches at the start of words count
+/// most. Returns the score and the char indices of `candidate` that matched, `None` when
+/// some character of `pattern` is missing.
+pub fn score(pattern: &str, candidate: &str) -> Option<(i64, Vec<usize>)> {
+    let chars: Vec<char> = candidate.chars().collect();
+    let pattern: Vec<char> = pattern.chars().filter(|c| !c.is_whitespace()).collect();
+    let mut matched: Vec<usize> = Vec::new();
+    let mut score = 0;
+    let mut from = 0;
+    for (k, &p) in pattern.iter().enumerate() {
+        let continues = |i: usize| matched.last().is_some_and(|m| m + 1 == i);
+        // a later match at a word start beats the first one, unless the first one continues
+        // a run, as long as the rest of the pattern still fits after it
+        let mut first = None;
+        let mut best = None;
+        for i in (from..chars.len()).filter(|i| eq_ignore_case(chars[*i], p)) {
+            if !subsequence(&pattern[k + 1..], &chars[i + 1..]) {
+                break;
+            }
+            first.get_or_insert(i);
+            if continues(i) {
+                best = Some(i);
+                break;
+            }
+            if best.is_none() && word_start(&chars, i) {
+                best = Some(i);
+            }
+        }
+        let i = best.or(first)?;
+        score += 1;
+        if continues(i) {
+            score += 10;
+        } else if word_start(&chars, i) {
+            score += 8;
+        }
+        score -= (i - from).min(3) as i64;
+        matched.push(i);
+        from = i + 1;
+    }
+    Some((score, matched))
+}
+
+fn subsequence(pattern: &[char], chars: &[char]) -> bool {
+    let mut chars = chars.iter();
+    pattern.iter().all(|p| chars.any(|c| eq_ignore_case(*c, *p)))
+}
+
+fn eq_ignore_case(a: char, b: char) -> bool {
+    a == b || a.to_lowercase().eq(b.to_lowercase())
+}
+
+/// the first letter of a word, after a separator or a lower case letter
+fn word_start(chars: &[char], i: usize) -> bool {
+    match i.checked_sub(1).map(|p| chars[p]) {
+        None => true,
+        Some(prev) => !prev.is_alphanumeric() || (prev.is_lowercase() && chars[i].is_uppercase()),
+    }
+}
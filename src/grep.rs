@@ -0,0 +1,114 @@
+//! Project grep: regex matches from every file under the project root, streamed into a
+//! read-only results buffer, optionally with the replacement each line would get.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use regex::Regex;
+
+use crate::finder::{self, Feed};
+
+/// A line of a file holding a match.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Match {
+    /// relative to the root that was searched
+    pub path: String,
+    /// numbered like the gutter
+    pub line: usize,
+    /// the line as it is in the file
+    pub text: String,
+}
+
+/// Start searching the files under `root` for `pattern`, files `.gitignore` excludes and
+/// binary files are skipped.
+pub fn search(root: PathBuf, pattern: Regex) -> Feed<Match> {
+    Feed::start(move |sink| {
+        for (path, rel) in finder::files(&root) {
+            let mut found = matches(&path, &rel, &pattern);
+            // the results buffer was closed
+            if !found.is_empty() && !sink.send(&mut found) {
+                return;
+            }
+        }
+    })
+}
+
+fn matches(path: &Path, rel: &str, pattern: &Regex) -> Vec<Match> {
+    let Ok(bytes) = fs::read(path) else {
+        return Vec::new();
+    };
+    if bytes.contains(&0) {
+        return Vec::new();
+    }
+    String::from_utf8_lossy(&bytes)
+        .lines()
+        .enumerate()
+        .filter(|(_, text)| pattern.is_match(text))
+        .map(|(line, text)| Match {
+            path: rel.to_owned(),
+            line,
+            text: text.to_owned(),
+        })
+        .collect()
+}
+
+/// What a results buffer shows and where its lines lead.
+#[derive(Clone, Debug)]
+pub struct Results {
+    pub root: PathBuf,
+    pub pattern: Regex,
+    /// replace mode: the matches are shown with what they would turn into
+    pub replacement: Option<String>,
+    /// file and line each line of the buffer leads to, none for the header
+    pub targets: Vec<Option<(String, usize)>>,
+    /// the matches shown so far, the ones a replacement is applied to
+    pub matches: Vec<Match>,
+    pub search: Feed<Match>,
+    /// matching lines found so far
+    pub count: usize,
+    /// files with matches found so far
+    pub files: usize,
+}
+impl Results {
+    pub fn new(root: PathBuf, pattern: Regex, replacement: Option<String>) -> Self {
+        Self {
+            search: search(root.clone(), pattern.clone()),
+            root,
+            pattern,
+            replacement,
+            targets: vec![None],
+            matches: Vec::new(),
+            count: 0,
+            files: 0,
+        }
+    }
+    /// the lines of the buffer for `m`, the one line found or in replace mode the line
+    /// before and after, tabs as spaces
+    pub fn lines(&self, m: &Match) -> Vec<String> {
+        let at = format!("{}:{}:", m.path, m.line);
+        let text = m.text.replace('\t', "    ");
+        match self.replacement {
+            Some(ref rep) => {
+                let new = self.pattern.replace_all(&m.text, rep.as_str());
+                let new = new.replace('\t', "    ");
+                vec![format!("{} - {}", at, text), format!("{} + {}", at, new)]
+            }
+            None => vec![format!("{} {}", at, text)],
+        }
+    }
+    /// first line of the buffer: what was searched, how much was found and the keys
+    pub fn header(&self) -> String {
+        let what = match self.replacement {
+            Some(ref rep) => format!("replace /{}/ with {:?}", self.pattern, rep),
+            None => format!("grep /{}/", self.pattern),
+        };
+        let keys = match (self.search.done(), &self.replacement) {
+            (false, _) => "searching...",
+            (true, Some(_)) => "Enter: open, a: apply to the buffers",
+            (true, None) => "Enter: open",
+        };
+        format!("{}: {} lines in {} files, {}", what, self.count, self.files, keys)
+    }
+}
@@ -0,0 +1,116 @@
+use std::path::Path;
+
+/// Editing rules that depend on the kind of file, picked by the extension of its path.
+#[derive(Debug)]
+pub struct Language {
+    pub name: &'static str,
+    pub extensions: &'static [&'static str],
+    /// a line ending in one of these indents the next line one level deeper
+    pub indent_after: &'static [char],
+    pub line_comment: Option<&'static str>,
+    /// (start, end) of a block comment, used when there is no line comment
+    pub block_comment: Option<(&'static str, &'static str)>,
+}
+
+const BRACES: &[char] = &['{', '(', '['];
+const C_BLOCK: Option<(&str, &str)> = Some(("/*", "*/"));
+const MARKUP_BLOCK: Option<(&str, &str)> = Some(("<!--", "-->"));
+
+static LANGUAGES: &[Language] = &[
+    Language {
+        name: "Rust",
+        extensions: &["rs"],
+        indent_after: BRACES,
+        line_comment: Some("//"),
+        block_comment: C_BLOCK,
+    },
+    Language {
+        name: "C",
+        extensions: &["c", "h", "cpp", "hpp", "cc", "cs", "java", "go", "swift", "kt"],
+        indent_after: BRACES,
+        line_comment: Some("//"),
+        block_comment: C_BLOCK,
+    },
+    Language {
+        name: "JavaScript",
+        extensions: &["js", "jsx", "ts", "tsx"],
+        indent_after: BRACES,
+        line_comment: Some("//"),
+        block_comment: C_BLOCK,
+    },
+    Language {
+        name: "JSON",
+        extensions: &["json"],
+        indent_after: BRACES,
+        line_comment: None,
+        block_comment: None,
+    },
+    Language {
+        name: "CSS",
+        extensions: &["css"],
+        indent_after: BRACES,
+        line_comment: None,
+        block_comment: C_BLOCK,
+    },
+    Language {
+        name: "Python",
+        extensions: &["py", "pyw"],
+        indent_after: &[':', '{', '(', '['],
+        line_comment: Some("#"),
+        block_comment: None,
+    },
+    Language {
+        name: "YAML",
+        extensions: &["yaml", "yml"],
+        indent_after: &[':'],
+        line_comment: Some("#"),
+        block_comment: None,
+    },
+    Language {
+        name: "TOML",
+        extensions: &["toml"],
+        indent_after: &['[', '{'],
+        line_comment: Some("#"),
+        block_comment: None,
+    },
+    Language {
+        name: "Shell",
+        extensions: &["sh", "bash", "zsh"],
+        indent_after: &['{', '('],
+        line_comment: Some("#"),
+        block_comment: None,
+    },
+    Language {
+        name: "SQL",
+        extensions: &["sql"],
+        indent_after: &['('],
+        line_comment: Some("--"),
+        block_comment: C_BLOCK,
+    },
+    Language {
+        name: "HTML",
+        extensions: &["html", "htm", "xml", "svg", "md"],
+        indent_after: &[],
+        line_comment: None,
+        block_comment: MARKUP_BLOCK,
+    },
+];
+
+/// files without a known extension only keep the indentation of the previous line
+pub static PLAIN: Language = Language {
+    name: "Plain text",
+    extensions: &[],
+    indent_after: &[],
+    line_comment: None,
+    block_comment: None,
+};
+
+pub fn for_path(path: &Path) -> &'static Language {
+    let Some(ext) = path.extension().and_then(|e| e.to_str()) else {
+        return &PLAIN;
+    };
+    LANGUAGES
+        .iter()
+        .find(|l| l.extensions.iter().any(|e| e.eq_ignore_ascii_case(ext)))
+        .unwrap_or(&PLAIN)
+}
@@ -0,0 +1,28 @@
+//! Editor core: buffers, key handling and rendering into a cell grid.
+//! Only `terminal` talks to the real terminal, everything else can run headless.
+
+pub mod cmdline;
+pub mod commands;
+pub mod crash;
+pub mod data;
+pub mod editor;
+pub mod encoding;
+pub mod error;
+pub mod ex;
+pub mod explorer;
+pub mod finder;
+pub mod fuzzy;
+pub mod grep;
+pub mod language;
+pub mod macros;
+pub mod paths;
+pub mod prompt_history;
+pub mod render;
+pub mod screen;
+pub mod session;
+pub mod state;
+pub mod terminal;
+
+pub const PREFIX_SIZE: usize = 5;
+pub const STATUS_SIZE: usize = 1;
+pub const MESSAGE_SIZE: usize = 1;
@@ -0,0 +1,174 @@
+//! Keyboard macros: key presses recorded into named registers and played back later.
+//! Registers are written to a TOML file as key names like `Ctrl+Left` or `a`.
+
+use std::{collections::BTreeMap, fs, io, path::PathBuf};
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::{Deserialize, Serialize};
+
+/// register used when a macro is recorded without a name
+pub const DEFAULT_REGISTER: &str = "default";
+
+#[derive(Clone, Debug, Default)]
+pub struct Macros {
+    /// name of the register being recorded and the keys pressed so far
+    pub recording: Option<(String, Vec<KeyEvent>)>,
+    /// set while a macro runs, macros can't be recorded or started from one
+    pub playing: bool,
+    /// register played when no name is given, the last one recorded
+    pub last: Option<String>,
+    registers: BTreeMap<String, Vec<KeyEvent>>,
+    /// file the registers are kept in, `None` keeps them for this session only
+    path: Option<PathBuf>,
+}
+
+/// what the registers file holds
+#[derive(Default, Deserialize, Serialize)]
+struct MacroFile {
+    last: Option<String>,
+    registers: BTreeMap<String, Vec<String>>,
+}
+
+impl Macros {
+    /// registers stored in `path`, none when it does not exist yet
+    pub fn load(path: PathBuf) -> io::Result<Self> {
+        let file: MacroFile = match fs::read_to_string(&path) {
+            Ok(text) => toml::from_str(&text)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.message()))?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => MacroFile::default(),
+            Err(e) => return Err(e),
+        };
+        let mut registers = BTreeMap::new();
+        for (name, keys) in file.registers {
+            let keys = keys
+                .iter()
+                .map(|k| {
+                    parse_key(k).ok_or_else(|| {
+                        io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            format!("unknown key \"{}\" in macro {}", k, name),
+                        )
+                    })
+                })
+                .collect::<io::Result<_>>()?;
+            registers.insert(name, keys);
+        }
+        Ok(Self {
+            last: file.last,
+            registers,
+            path: Some(path),
+            ..Self::default()
+        })
+    }
+    pub fn get(&self, name: &str) -> Option<&[KeyEvent]> {
+        self.registers.get(name).map(|k| k.as_slice())
+    }
+    /// start recording into register `name`, an empty name records into the default one
+    pub fn start(&mut self, name: &str) {
+        let name = if name.is_empty() {
+            DEFAULT_REGISTER
+        } else {
+            name
+        };
+        self.recording = Some((name.to_owned(), Vec::new()));
+    }
+    /// remember a key pressed while recording
+    pub fn record(&mut self, ke: KeyEvent) {
+        if let Some((_, ref mut keys)) = self.recording {
+            keys.push(ke);
+        }
+    }
+    /// store the recorded keys in their register and write the registers file,
+    /// the name and the number of keys recorded
+    pub fn stop(&mut self) -> io::Result<Option<(String, usize)>> {
+        let Some((name, keys)) = self.recording.take() else {
+            return Ok(None);
+        };
+        let count = keys.len();
+        self.registers.insert(name.clone(), keys);
+        self.last = Some(name.clone());
+        self.save()?;
+        Ok(Some((name, count)))
+    }
+    fn save(&self) -> io::Result<()> {
+        let Some(ref path) = self.path else {
+            return Ok(());
+        };
+        let file = MacroFile {
+            last: self.last.clone(),
+            registers: self
+                .registers
+                .iter()
+                .map(|(name, keys)| (name.clone(), keys.iter().filter_map(key_name).collect()))
+                .collect(),
+        };
+        let text = toml::to_string(&file)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        fs::write(path, text)
+    }
+}
+
+const NAMED_KEYS: &[(&str, KeyCode)] = &[
+    ("Enter", KeyCode::Enter),
+    ("Tab", KeyCode::Tab),
+    ("BackTab", KeyCode::BackTab),
+    ("Backspace", KeyCode::Backspace),
+    ("Delete", KeyCode::Delete),
+    ("Insert", KeyCode::Insert),
+    ("Esc", KeyCode::Esc),
+    ("Left", KeyCode::Left),
+    ("Right", KeyCode::Right),
+    ("Up", KeyCode::Up),
+    ("Down", KeyCode::Down),
+    ("Home", KeyCode::Home),
+    ("End", KeyCode::End),
+    ("PageUp", KeyCode::PageUp),
+    ("PageDown", KeyCode::PageDown),
+    ("Space", KeyCode::Char(' ')),
+];
+
+const MODIFIERS: &[(&str, KeyModifiers)] = &[
+    ("Ctrl+", KeyModifiers::CONTROL),
+    ("Alt+", KeyModifiers::ALT),
+    ("Shift+", KeyModifiers::SHIFT),
+];
+
+/// the key written like `Ctrl+Shift+K`, `None` for keys that have no name
+pub fn key_name(ke: &KeyEvent) -> Option<String> {
+    let key = match ke.code {
+        KeyCode::F(n) => format!("F{}", n),
+        KeyCode::Char(c) if c != ' ' => c.to_string(),
+        code => NAMED_KEYS.iter().find(|(_, k)| *k == code)?.0.to_owned(),
+    };
+    let mut name = String::new();
+    for (prefix, m) in MODIFIERS {
+        if ke.modifiers.contains(*m) {
+            name.push_str(prefix);
+        }
+    }
+    name.push_str(&key);
+    Some(name)
+}
+
+/// read a key written by `key_name`
+pub fn parse_key(mut name: &str) -> Option<KeyEvent> {
+    let mut modifiers = KeyModifiers::NONE;
+    // a lone `+` is the key itself, not a modifier separator
+    while let Some((rest, m)) = MODIFIERS
+        .iter()
+        .find_map(|(p, m)| Some((name.strip_prefix(p)?, *m)))
+        .filter(|(rest, _)| !rest.is_empty())
+    {
+        modifiers |= m;
+        name = rest;
+    }
+    let mut chars = name.chars();
+    let code = match (chars.next(), chars.next()) {
+        (Some(c), None) => KeyCode::Char(c),
+        _ => match NAMED_KEYS.iter().find(|(n, _)| *n == name) {
+            Some((_, k)) => *k,
+            None => KeyCode::F(name.strip_prefix('F')?.parse().ok()?),
+        },
+    };
+    Some(KeyEvent::new(code, modifiers))
+}
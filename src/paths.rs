@@ -0,0 +1,88 @@
+//! Paths typed into prompts: `~` expansion, resolving against a directory and completion
+//! from the entries of the directory being typed.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// a leading `~` is the home directory
+pub fn expand_home(path: &str) -> PathBuf {
+    let home = || directories::BaseDirs::new().map(|d| d.home_dir().to_path_buf());
+    match path.strip_prefix('~') {
+        Some(rest) if rest.is_empty() || rest.starts_with('/') => match home() {
+            Some(home) => home.join(rest.trim_start_matches('/')),
+            None => PathBuf::from(path),
+        },
+        _ => PathBuf::from(path),
+    }
+}
+
+/// `text` as a path, relative ones start in `base`
+pub fn resolve(base: &Path, text: &str) -> PathBuf {
+    let path = expand_home(text);
+    if path.is_absolute() {
+        path
+    } else {
+        base.join(path)
+    }
+}
+
+/// `text` split after its last `/` into the directory part and the name being typed
+pub fn split(text: &str) -> (&str, &str) {
+    match text.rfind('/') {
+        Some(i) => (&text[..=i], &text[i + 1..]),
+        None => ("", text),
+    }
+}
+
+/// A directory entry whose name starts with what is typed after the last `/` of `text`,
+/// directories end with `/`. Hidden entries only show when a `.` was typed.
+pub fn candidates(base: &Path, text: &str) -> Vec<String> {
+    let (dir, prefix) = split(text);
+    let dir = match resolve(base, dir) {
+        d if d.as_os_str().is_empty() => PathBuf::from("."),
+        d => d,
+    };
+    let Ok(read) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut names: Vec<String> = read
+        .filter_map(|e| {
+            let e = e.ok()?;
+            let mut name = e.file_name().into_string().ok()?;
+            // a link to a directory completes like one
+            if e.path().is_dir() {
+                name.push('/');
+            }
+            Some(name)
+        })
+        .filter(|name| name.starts_with(prefix))
+        .filter(|name| prefix.starts_with('.') || !name.starts_with('.'))
+        .collect();
+    names.sort();
+    names
+}
+
+/// `text` with the name after its last `/` extended as far as all candidates agree,
+/// `None` when no entry matches
+pub fn complete(base: &Path, text: &str) -> Option<String> {
+    let (dir, _) = split(text);
+    let names = candidates(base, text);
+    Some(format!("{}{}", dir, common_prefix(names.into_iter())?))
+}
+
+/// the longest start all of `words` share, `None` when there are none
+pub fn common_prefix(mut words: impl Iterator<Item = String>) -> Option<String> {
+    let mut prefix = words.next()?;
+    for w in words {
+        let len = prefix
+            .char_indices()
+            .zip(w.chars())
+            .take_while(|((_, a), b)| a == b)
+            .last()
+            .map_or(0, |((i, a), _)| i + a.len_utf8());
+        prefix.truncate(len);
+    }
+    Some(prefix)
+}
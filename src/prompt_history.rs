@@ -0,0 +1,69 @@
+//! What was typed into each kind of prompt, recalled with Up and Down and kept in a TOML
+//! file between sessions.
+
+use std::{collections::BTreeMap, fs, io, path::PathBuf};
+
+use crate::data::PromptType;
+
+/// entries kept for each kind of prompt, the oldest are dropped first
+pub const HISTORY_LEN: usize = 100;
+
+#[derive(Clone, Debug, Default)]
+pub struct PromptHistory {
+    /// entries for each prompt kind, oldest first
+    entries: BTreeMap<String, Vec<String>>,
+    /// file the entries are kept in, `None` keeps them for this session only
+    path: Option<PathBuf>,
+}
+
+impl PromptHistory {
+    /// entries stored in `path`, none when it does not exist yet
+    pub fn load(path: PathBuf) -> io::Result<Self> {
+        let entries = match fs::read_to_string(&path) {
+            Ok(text) => toml::from_str(&text)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.message()))?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(e) => return Err(e),
+        };
+        Ok(Self {
+            entries,
+            path: Some(path),
+        })
+    }
+    /// the entries of prompts of type `p_type`, oldest first
+    pub fn entries(&self, p_type: PromptType) -> &[String] {
+        p_type
+            .history_key()
+            .and_then(|k| self.entries.get(k))
+            .map_or(&[], |e| e.as_slice())
+    }
+    /// the newest entry of prompts of type `p_type`
+    pub fn last(&self, p_type: PromptType) -> Option<&str> {
+        self.entries(p_type).last().map(String::as_str)
+    }
+    /// remember `text` as the newest entry, an older copy of it is removed,
+    /// then write the file
+    pub fn add(&mut self, p_type: PromptType, text: &str) -> io::Result<()> {
+        let Some(key) = p_type.history_key() else {
+            return Ok(());
+        };
+        if text.trim().is_empty() {
+            return Ok(());
+        }
+        let entries = self.entries.entry(key.to_owned()).or_default();
+        entries.retain(|e| e != text);
+        entries.push(text.to_owned());
+        if entries.len() > HISTORY_LEN {
+            entries.drain(..entries.len() - HISTORY_LEN);
+        }
+        self.save()
+    }
+    fn save(&self) -> io::Result<()> {
+        let Some(ref path) = self.path else {
+            return Ok(());
+        };
+        let text = toml::to_string(&self.entries)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        fs::write(path, text)
+    }
+}
@@ -0,0 +1,262 @@
+use std::{cmp::min, path::Path};
+
+use crossterm::style::{ContentStyle, Stylize};
+
+use crate::{
+    cmdline::Config,
+    data::{FileData, FileStatus, List, Prompt},
+    explorer::{Explorer, EXPLORER_WIDTH},
+    screen::Grid,
+    MESSAGE_SIZE, PREFIX_SIZE, STATUS_SIZE,
+};
+
+/// Draw the whole editor, the buffer with the prompt over the message line if one is open.
+pub fn draw(data: &FileData, config: &Config, grid: &mut Grid) {
+    normal_write(data, config, grid);
+    if let Some(e) = data.explorer.as_ref().filter(|e| e.visible) {
+        explorer_write(e, data.text_height(), grid);
+    }
+    if let Some(ref p) = data.prompt {
+        prompt_write(p, grid);
+    }
+}
+
+pub fn status_style() -> ContentStyle {
+    ContentStyle::new().bold().on_dark_grey().white()
+}
+pub fn selection_style() -> ContentStyle {
+    ContentStyle::new().reverse()
+}
+pub fn extra_cursor_style() -> ContentStyle {
+    ContentStyle::new().reverse().underlined()
+}
+pub fn bracket_style() -> ContentStyle {
+    ContentStyle::new().bold().underlined()
+}
+pub fn list_style() -> ContentStyle {
+    ContentStyle::new().on_dark_grey().white()
+}
+
+/// most entries of a list shown at once
+pub const LIST_ROWS: usize = 10;
+pub fn normal_write(data: &FileData, config: &Config, grid: &mut Grid) {
+    let vstart = data.top_visible;
+    let count = min(
+        data.size.0 - STATUS_SIZE - MESSAGE_SIZE,
+        data.lines.len() - vstart,
+    );
+    let f_name = if let Some(ref f) = config.file {
+        format!("file: {}", f.display())
+            + if data.f_status == FileStatus::Edited {
+                "*"
+            } else {
+                " "
+            }
+    } else {
+        "No file selected".to_owned()
+    };
+    let recording = match data.macros.recording {
+        Some((ref name, _)) => format!("[REC {}] ", name),
+        None => String::new(),
+    };
+    let buffers = match data.buffers.len() {
+        0 => String::new(),
+        n => format!("[{} buffers] ", n + 1),
+    };
+    let time = format!(
+        "{}{}{}{} time: {}",
+        buffers,
+        recording,
+        if data.read_only { "[RO] " } else { "" },
+        data.encoding.name(),
+        chrono::Local::now().format("%H:%M:%S")
+    );
+    grid.fill(0, 0, status_style());
+    grid.put_str(0, 0, &f_name, status_style());
+    let l = time.chars().count();
+    let fl = f_name.chars().count();
+    if fl + l < data.size.1 {
+        grid.put_str(0, data.size.1 - l, &time, status_style());
+    }
+
+    let selections: Vec<_> = std::iter::once(data.cursor())
+        .chain(data.cursors.iter().copied())
+        .filter_map(|c| c.selection())
+        .collect();
+    // the explorer pane, when shown, comes before the line numbers
+    let left = data.text_left();
+    let tab_size = data.tab_size;
+    for (idx, l) in data.lines[vstart..].iter().enumerate().take(count) {
+        let row = idx + STATUS_SIZE;
+        let number = format!("{:0>3}| ", idx + vstart);
+        let col = grid.put_str(row, left - PREFIX_SIZE, &number, ContentStyle::new());
+        let shown: String = l.expand_tabs(tab_size).chars().skip(data.left_visible).collect();
+        grid.put_str(row, col, &shown, ContentStyle::new());
+        for (start, end) in selections
+            .iter()
+            .filter(|(s, e)| (s.0..=e.0).contains(&(idx + vstart)))
+        {
+            let from = if idx + vstart == start.0 {
+                l.display_col(start.1, tab_size)
+            } else {
+                0
+            };
+            // the selected line break shows as one cell after the end of the line
+            let to = if idx + vstart == end.0 {
+                l.display_col(end.1, tab_size)
+            } else {
+                l.display_col(l.len(), tab_size) + 1
+            };
+            for c in from.max(data.left_visible)..to {
+                grid.restyle(row, c + left - data.left_visible, selection_style());
+            }
+        }
+        // a block covers the same columns on every line, also past the end of short lines
+        if let Some(b) = data.block.filter(|b| b.rows().contains(&(idx + vstart))) {
+            let cols = b.cols();
+            if cols.is_empty() && idx + vstart != b.head.0 && cols.start >= data.left_visible {
+                let col = cols.start + left - data.left_visible;
+                grid.restyle(row, col, extra_cursor_style());
+            }
+            for c in cols.start.max(data.left_visible)..cols.end {
+                grid.restyle(row, c + left - data.left_visible, selection_style());
+            }
+        }
+    }
+    // the terminal has one cursor, the extra ones are drawn as cells
+    for c in data.cursors.iter() {
+        let (row, col) = (c.location.0, c.location.1);
+        let col = data.lines[row].display_col(col, tab_size);
+        if (vstart..vstart + count).contains(&row) && col >= data.left_visible {
+            grid.restyle(
+                row - vstart + STATUS_SIZE,
+                col + left - data.left_visible,
+                extra_cursor_style(),
+            );
+        }
+    }
+    if data.prompt.is_none() {
+        for p in data.bracket_pair().into_iter().flat_map(|(a, b)| [a, b]) {
+            let col = data.lines[p.0].display_col(p.1, tab_size);
+            if (vstart..vstart + count).contains(&p.0) && col >= data.left_visible {
+                grid.restyle(
+                    p.0 - vstart + STATUS_SIZE,
+                    col + left - data.left_visible,
+                    bracket_style(),
+                );
+            }
+        }
+    }
+    for row in count..(data.size.0 - STATUS_SIZE - MESSAGE_SIZE) {
+        grid.put_str(row + STATUS_SIZE, left - PREFIX_SIZE, "~", ContentStyle::new());
+    }
+    if let Some(t) = data.message.show() {
+        grid.put_str(data.size.0 - MESSAGE_SIZE, 0, t, status_style());
+    }
+
+    let col = data.block.map_or(data.display_col(), |b| b.head.1);
+    grid.cursor = Some((
+        data.location.0 - data.top_visible + STATUS_SIZE,
+        col + left - data.left_visible,
+    ));
+}
+/// Draw the tree of the explorer on the `height` text rows left of the text, folders with
+/// an arrow showing whether they are open, the selected entry highlighted while focused.
+pub fn explorer_write(e: &Explorer, height: usize, grid: &mut Grid) {
+    let width = EXPLORER_WIDTH - 1;
+    let blank = " ".repeat(width);
+    for row in STATUS_SIZE..STATUS_SIZE + height {
+        grid.put_str(row, 0, &blank, ContentStyle::new());
+        grid.put_str(row, width, "│", ContentStyle::new().dark_grey());
+    }
+    for (n, entry) in e.entries.iter().enumerate().skip(e.top).take(height) {
+        let row = n - e.top + STATUS_SIZE;
+        let style = match (n == e.selected, e.focused) {
+            (true, true) => selection_style(),
+            (true, false) => ContentStyle::new().bold(),
+            _ if entry.is_dir => ContentStyle::new().blue(),
+            _ => ContentStyle::new(),
+        };
+        let arrow = match entry.is_dir {
+            true if e.is_expanded(&entry.path) => "▾ ",
+            true => "▸ ",
+            false => "  ",
+        };
+        let text: String = format!("{}{}{}", "  ".repeat(entry.depth), arrow, entry.name())
+            .chars()
+            .take(width)
+            .collect();
+        grid.put_str(row, 0, &text, style);
+    }
+}
+
+pub fn prompt_write(prompt: &Prompt, grid: &mut Grid) {
+    let row = grid.height - 1;
+    if let Some(ref list) = prompt.list {
+        let top = list_write(list, row, grid);
+        if let Some((ref path, ref lines)) = prompt.preview {
+            preview_write(path, lines, top, grid);
+        }
+    }
+    let offset = prompt.message.char_len + 1;
+    grid.fill(row, 0, ContentStyle::new());
+    grid.put_str(row, 0, &prompt.message.data, ContentStyle::new());
+    grid.put_str(
+        row,
+        offset,
+        &prompt.data.data[prompt.left_visible..],
+        ContentStyle::new(),
+    );
+    grid.cursor = Some((row, offset + prompt.cursor - prompt.left_visible));
+}
+
+/// Draw the entries of `list` on the rows right above `bottom`, the selected one
+/// highlighted and scrolled into view, matched characters in bold. Returns the top row.
+pub fn list_write(list: &List, bottom: usize, grid: &mut Grid) -> usize {
+    let rows = min(LIST_ROWS, bottom.saturating_sub(STATUS_SIZE));
+    if list.shown.is_empty() {
+        if rows > 0 {
+            grid.fill(bottom - 1, 0, list_style());
+            grid.put_str(bottom - 1, 1, "No matches", list_style().italic());
+            return bottom - 1;
+        }
+        return bottom;
+    }
+    let count = min(rows, list.shown.len());
+    let top = list.selected.saturating_sub(count - 1);
+    for (n, (i, cols)) in list.shown[top..top + count].iter().enumerate() {
+        let row = bottom - count + n;
+        let style = if top + n == list.selected {
+            selection_style()
+        } else {
+            list_style()
+        };
+        let item = &list.items[*i];
+        grid.fill(row, 0, style);
+        let detail = item.detail.chars().count();
+        if detail + 2 < grid.width {
+            grid.put_str(row, grid.width - detail - 1, &item.detail, style);
+        }
+        grid.put_str(row, 1, &item.label, style);
+        for col in cols {
+            grid.restyle(row, col + 1, style.bold());
+        }
+    }
+    bottom - count
+}
+
+/// Draw the first lines of the file at `path` over the text rows above `bottom`, under a
+/// row naming it.
+pub fn preview_write(path: &Path, lines: &[String], bottom: usize, grid: &mut Grid) {
+    if bottom <= STATUS_SIZE {
+        return;
+    }
+    grid.fill(STATUS_SIZE, 0, list_style());
+    grid.put_str(STATUS_SIZE, 1, &path.display().to_string(), list_style().bold());
+    for row in STATUS_SIZE + 1..bottom {
+        grid.fill(row, 0, ContentStyle::new());
+        if let Some(line) = lines.get(row - STATUS_SIZE - 1) {
+            grid.put_str(row, 0, line, ContentStyle::new());
+        }
+    }
+}
@@ -0,0 +1,173 @@
+use std::{
+    cmp::min,
+    io::{self, Write},
+};
+
+use crossterm::{
+    cursor::{Hide, MoveTo, Show},
+    queue,
+    style::{ContentStyle, PrintStyledContent},
+    terminal::{BeginSynchronizedUpdate, Clear, ClearType, EndSynchronizedUpdate},
+};
+
+/// longest stretch of unchanged cells that is reprinted to keep a run going
+const GAP: usize = 4;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Cell {
+    pub c: char,
+    pub style: ContentStyle,
+}
+impl Default for Cell {
+    fn default() -> Self {
+        Self {
+            c: ' ',
+            style: ContentStyle::new(),
+        }
+    }
+}
+
+/// One frame of the terminal: `height` rows of `width` styled cells and the cursor position.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Grid {
+    pub width: usize,
+    pub height: usize,
+    cells: Vec<Cell>,
+    /// (row, column) of the cursor, `None` hides it
+    pub cursor: Option<(usize, usize)>,
+}
+impl Grid {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            cells: vec![Cell::default(); width * height],
+            cursor: None,
+        }
+    }
+    pub fn clear(&mut self) {
+        self.cells.fill(Cell::default());
+        self.cursor = None;
+    }
+    pub fn set(&mut self, row: usize, col: usize, cell: Cell) {
+        if row < self.height && col < self.width {
+            self.cells[row * self.width + col] = cell;
+        }
+    }
+    /// writes `text` starting at `col`, clipped to the width of the grid, returns the column after it
+    pub fn put_str(&mut self, row: usize, col: usize, text: &str, style: ContentStyle) -> usize {
+        let mut col = col;
+        for c in text.chars() {
+            if col >= self.width {
+                break;
+            }
+            // control characters would move the terminal cursor
+            let c = if c.is_control() {
+                char::REPLACEMENT_CHARACTER
+            } else {
+                c
+            };
+            self.set(row, col, Cell { c, style });
+            col += 1;
+        }
+        col
+    }
+    /// changes the style of a cell and keeps its character
+    pub fn restyle(&mut self, row: usize, col: usize, style: ContentStyle) {
+        if row < self.height && col < self.width {
+            self.cells[row * self.width + col].style = style;
+        }
+    }
+    /// fills the row with blanks of `style` from `col` to the right edge
+    pub fn fill(&mut self, row: usize, col: usize, style: ContentStyle) {
+        for c in col..self.width {
+            self.set(row, c, Cell { c: ' ', style });
+        }
+    }
+    pub fn row(&self, row: usize) -> &[Cell] {
+        &self.cells[row * self.width..(row + 1) * self.width]
+    }
+    /// the characters of a row without styling
+    #[allow(unused)]
+    pub fn row_text(&self, row: usize) -> String {
+        self.row(row).iter().map(|c| c.c).collect()
+    }
+}
+
+/// Double buffered screen, the new frame is drawn into the back grid and `present`
+/// only sends the cells that differ from what is already on the terminal.
+pub struct Screen {
+    front: Option<Grid>,
+    back: Grid,
+    sync_update: bool,
+}
+impl Screen {
+    pub fn new(sync_update: bool) -> Self {
+        Self {
+            front: None,
+            back: Grid::new(0, 0),
+            sync_update,
+        }
+    }
+    /// clears the back buffer for a frame of the given size
+    pub fn frame(&mut self, width: usize, height: usize) -> &mut Grid {
+        if self.back.width != width || self.back.height != height {
+            self.back = Grid::new(width, height);
+        } else {
+            self.back.clear();
+        }
+        &mut self.back
+    }
+    pub fn present(&mut self, out: &mut impl Write) -> io::Result<()> {
+        if self.sync_update {
+            queue!(out, BeginSynchronizedUpdate)?;
+        }
+        queue!(out, Hide)?;
+        let full = match self.front {
+            Some(ref f) => f.width != self.back.width || f.height != self.back.height,
+            None => true,
+        };
+        if full {
+            queue!(out, Clear(ClearType::All))?;
+        }
+        for row in 0..self.back.height {
+            let new = self.back.row(row);
+            let old = if full {
+                None
+            } else {
+                self.front.as_ref().map(|f| f.row(row))
+            };
+            let changed = |col: usize| !old.is_some_and(|o| o[col] == new[col]);
+            // unchanged cells shorter than a cursor move are reprinted instead of skipped
+            let in_run = |col: usize| (col..min(col + GAP, new.len())).any(changed);
+            let mut col = 0;
+            while col < new.len() {
+                if !changed(col) {
+                    col += 1;
+                    continue;
+                }
+                // a run of changed cells, split into pieces of the same style
+                queue!(out, MoveTo(col as u16, row as u16))?;
+                while col < new.len() && in_run(col) {
+                    let style = new[col].style;
+                    let mut text = String::new();
+                    while col < new.len() && new[col].style == style && in_run(col) {
+                        text.push(new[col].c);
+                        col += 1;
+                    }
+                    queue!(out, PrintStyledContent(style.apply(text)))?;
+                }
+            }
+        }
+        if let Some((row, col)) = self.back.cursor {
+            queue!(out, MoveTo(col as u16, row as u16), Show)?;
+        }
+        if self.sync_update {
+            queue!(out, EndSynchronizedUpdate)?;
+        }
+        out.flush()?;
+        let old = self.front.take().unwrap_or_else(|| Grid::new(0, 0));
+        self.front = Some(std::mem::replace(&mut self.back, old));
+        Ok(())
+    }
+}
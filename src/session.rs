@@ -0,0 +1,135 @@
+//! Where each file was left and which files were open, kept in a TOML file between
+//! sessions so a file opens at the same place and `--restore` brings the buffers back.
+
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    data::{FileData, TextPos},
+    editor::scroll,
+};
+
+/// files whose place is kept, the ones left longest ago are forgotten first
+pub const SESSION_FILES: usize = 1000;
+
+/// The cursor and the scroll position of a file.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Place {
+    /// line and byte offset, like `FileData::location`
+    pub location: (usize, usize),
+    pub top_visible: usize,
+    pub left_visible: usize,
+    /// when the file was left, in seconds since the epoch
+    used: i64,
+}
+impl Place {
+    pub fn of(b: &FileData) -> Self {
+        Self {
+            location: (b.location.0, b.location.1),
+            top_visible: b.top_visible,
+            left_visible: b.left_visible,
+            used: chrono::Local::now().timestamp(),
+        }
+    }
+    /// move the cursor of `b` here, kept inside the text in case the file got shorter
+    pub fn apply(&self, b: &mut FileData) {
+        let row = self.location.0.min(b.lines.len() - 1);
+        let line = &b.lines[row].data;
+        let col = line.floor_char_boundary(self.location.1.min(line.len()));
+        b.anchor = None;
+        b.cursors.clear();
+        b.move_to(TextPos(row, col));
+        b.top_visible = self.top_visible.min(row);
+        b.left_visible = self.left_visible;
+        scroll(b);
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct Stored {
+    /// by absolute path
+    files: BTreeMap<String, Place>,
+    /// the buffers open at the end of the last session, the shown one first
+    open: Vec<String>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Session {
+    stored: Stored,
+    /// file the session is kept in, `None` keeps it for this run only
+    path: Option<PathBuf>,
+}
+
+impl Session {
+    /// the session stored in `path`, an empty one when it does not exist yet
+    pub fn load(path: PathBuf) -> io::Result<Self> {
+        let stored = match fs::read_to_string(&path) {
+            Ok(text) => toml::from_str(&text)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.message()))?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Stored::default(),
+            Err(e) => return Err(e),
+        };
+        Ok(Self {
+            stored,
+            path: Some(path),
+        })
+    }
+    /// where `file` was left
+    pub fn place(&self, file: &Path) -> Option<Place> {
+        self.stored.files.get(&key(file)?).copied()
+    }
+    /// the files open at the end of the last session, the shown one first
+    pub fn open_files(&self) -> Vec<PathBuf> {
+        self.stored.open.iter().map(PathBuf::from).collect()
+    }
+    /// remember that `file` was left at `place`, written by the next `save`
+    pub fn remember(&mut self, file: &Path, place: Place) {
+        let Some(key) = key(file) else {
+            return;
+        };
+        self.stored.files.insert(key, place);
+        if self.stored.files.len() > SESSION_FILES {
+            let oldest = self.stored.files.iter().min_by_key(|(_, p)| p.used);
+            if let Some(k) = oldest.map(|(k, _)| k.clone()) {
+                self.stored.files.remove(&k);
+            }
+        }
+    }
+    /// Remember the place of every buffer of `w` and that they are the open ones, then
+    /// write the file.
+    pub fn save(w: &mut FileData) -> io::Result<()> {
+        let buffers: Vec<(PathBuf, Place)> = std::iter::once(&*w)
+            .chain(w.buffers.iter())
+            .filter(|b| !b.path.as_os_str().is_empty())
+            .map(|b| (b.path.clone(), Place::of(b)))
+            .collect();
+        let session = &mut w.session;
+        session.stored.open = buffers.iter().filter_map(|(path, _)| key(path)).collect();
+        for (path, place) in buffers {
+            session.remember(&path, place);
+        }
+        let Some(ref path) = session.path else {
+            return Ok(());
+        };
+        let text = toml::to_string(&session.stored)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        fs::write(path, text)
+    }
+}
+
+/// `file` as an absolute path, the same file has one key however it was named
+fn key(file: &Path) -> Option<String> {
+    if file.as_os_str().is_empty() {
+        return None;
+    }
+    let path = fs::canonicalize(file)
+        .or_else(|_| std::env::current_dir().map(|cwd| cwd.join(file)))
+        .ok()?;
+    Some(path.to_string_lossy().into_owned())
+}
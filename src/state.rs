@@ -0,0 +1,27 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use directories::ProjectDirs;
+
+/// Directory for files the editor keeps between runs, `None` when the platform has no home directory.
+pub fn state_dir() -> Option<PathBuf> {
+    let dirs = ProjectDirs::from("", "", "te")?;
+    Some(
+        dirs.state_dir()
+            .unwrap_or(dirs.data_local_dir())
+            .to_path_buf(),
+    )
+}
+
+/// path of `name` inside the state directory, the directories leading to it are created
+pub fn state_path(name: impl AsRef<Path>) -> io::Result<PathBuf> {
+    let path = state_dir()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no state directory"))?
+        .join(name);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    Ok(path)
+}
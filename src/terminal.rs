@@ -0,0 +1,68 @@
+use std::{
+    io::stdout,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use crossterm::{
+    cursor::Show,
+    event::{
+        DisableMouseCapture, EnableMouseCapture, KeyboardEnhancementFlags,
+        PopKeyboardEnhancementFlags, PushKeyboardEnhancementFlags,
+    },
+    execute,
+    terminal::{Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen},
+};
+
+use crate::{data::TextPos, error};
+
+/// set while the terminal is in raw mode, `restore` only undoes the setup once
+static ACTIVE: AtomicBool = AtomicBool::new(false);
+/// the editor draws on the alternate screen
+static ALTERNATE: AtomicBool = AtomicBool::new(false);
+
+/// Raw mode, keyboard enhancement flags, mouse capture and the alternate screen,
+/// undone on drop so every exit path restores the terminal.
+pub struct TerminalGuard;
+impl TerminalGuard {
+    /// with `alternate` false the editor draws over the main screen and clears it on exit
+    pub fn enter(alternate: bool) -> error::Result<Self> {
+        crossterm::terminal::enable_raw_mode()?;
+        ACTIVE.store(true, Ordering::SeqCst);
+        let guard = TerminalGuard;
+        if alternate {
+            execute!(stdout(), EnterAlternateScreen)?;
+            ALTERNATE.store(true, Ordering::SeqCst);
+        }
+        execute!(
+            stdout(),
+            PushKeyboardEnhancementFlags(KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES),
+            EnableMouseCapture
+        )?;
+        Ok(guard)
+    }
+}
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        restore();
+    }
+}
+
+/// Put the terminal back into the state the shell expects, safe to call from a panic hook.
+pub fn restore() {
+    if !ACTIVE.swap(false, Ordering::SeqCst) {
+        return;
+    }
+    execute!(stdout(), PopKeyboardEnhancementFlags, DisableMouseCapture).unwrap_or(());
+    crossterm::terminal::disable_raw_mode().unwrap_or(());
+    if ALTERNATE.swap(false, Ordering::SeqCst) {
+        execute!(stdout(), LeaveAlternateScreen, Show).unwrap_or(());
+    } else {
+        execute!(stdout(), Clear(ClearType::All), Show).unwrap_or(());
+    }
+}
+
+/// size of the terminal as (rows, columns)
+pub fn size() -> error::Result<TextPos> {
+    let (w, h) = crossterm::terminal::size()?;
+    Ok(TextPos(h.into(), w.into()))
+}
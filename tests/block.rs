@@ -0,0 +1,97 @@
+mod harness;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use harness::{ctrl, key, Harness};
+
+fn alt_shift(code: KeyCode) -> KeyEvent {
+    KeyEvent::new(code, KeyModifiers::ALT | KeyModifiers::SHIFT)
+}
+
+#[test]
+fn typing_in_a_block_pads_short_lines() {
+    let mut h = Harness::new("abcd\nx\nefgh");
+    h.keys([
+        key(KeyCode::Right),
+        key(KeyCode::Right),
+        alt_shift(KeyCode::Down),
+        alt_shift(KeyCode::Down),
+        alt_shift(KeyCode::Right),
+    ]);
+    h.type_str("|");
+    assert_eq!(h.text(), "ab|d\nx |\nef|h");
+    assert_eq!(h.cursor(), (2, 3));
+    // the block is now zero wide, Backspace removes the typed column
+    h.press(key(KeyCode::Backspace));
+    assert_eq!(h.text(), "abd\nx \nefh");
+    h.press(ctrl(KeyCode::Char('z')));
+    assert_eq!(h.text(), "ab|d\nx |\nef|h");
+}
+
+#[test]
+fn copy_and_paste_a_block() {
+    let mut h = Harness::new("abc\ndef\n");
+    h.keys([
+        alt_shift(KeyCode::Right),
+        alt_shift(KeyCode::Right),
+        alt_shift(KeyCode::Down),
+        ctrl(KeyCode::Char('c')),
+        key(KeyCode::Esc),
+        key(KeyCode::End),
+    ]);
+    h.press(ctrl(KeyCode::Char('v')));
+    assert_eq!(h.text(), "abc\ndefab\n   de");
+    assert_eq!(h.cursor(), (2, 5));
+}
+
+#[test]
+fn cut_removes_the_columns() {
+    let mut h = Harness::new("abc\nd\nefg");
+    h.keys([
+        key(KeyCode::Right),
+        alt_shift(KeyCode::Right),
+        alt_shift(KeyCode::Down),
+        alt_shift(KeyCode::Down),
+        ctrl(KeyCode::Char('x')),
+    ]);
+    assert_eq!(h.text(), "ac\nd\neg");
+    assert_eq!(h.data.block_text(h.data.block.unwrap()), ["", "", ""]);
+    h.press(ctrl(KeyCode::Char('z')));
+    assert_eq!(h.text(), "abc\nd\nefg");
+}
+
+#[test]
+fn plain_text_paste_fills_each_line_of_a_block() {
+    let mut h = Harness::new("ab\ncd");
+    h.keys([
+        key(KeyCode::Right),
+        KeyEvent::new(KeyCode::Left, KeyModifiers::SHIFT),
+        ctrl(KeyCode::Char('c')),
+        key(KeyCode::Right),
+        key(KeyCode::Right),
+        alt_shift(KeyCode::Down),
+        ctrl(KeyCode::Char('v')),
+    ]);
+    assert_eq!(h.text(), "aba\ncda");
+}
+
+#[test]
+fn alt_drag_draws_a_block_past_line_ends() {
+    let mut h = Harness::new("abcdef\nx\nabcdef");
+    // screen row 1 is line 0, text starts after the 5 column gutter
+    h.click(1, 6, KeyModifiers::NONE);
+    h.drag(3, 9, KeyModifiers::ALT);
+    assert_eq!(h.data.block_text(h.data.block.unwrap()), ["bcd", "", "bcd"]);
+    assert_eq!(h.screen_cursor(), Some((3, 9)));
+    let mut grid = text_editor::screen::Grid::new(40, 10);
+    text_editor::render::draw(&h.data, &h.config, &mut grid);
+    let selected = text_editor::render::selection_style();
+    let styled: Vec<bool> = grid.row(2)[5..10]
+        .iter()
+        .map(|c| c.style == selected)
+        .collect();
+    assert_eq!(styled, [false, true, true, true, false]);
+    // a plain drag selects text instead
+    h.drag(1, 8, KeyModifiers::NONE);
+    assert!(h.data.block.is_none());
+    assert!(h.data.selection().is_some());
+}
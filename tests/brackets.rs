@@ -0,0 +1,72 @@
+mod harness;
+
+use crossterm::event::KeyCode;
+use harness::{ctrl, key, Harness};
+use text_editor::{render, screen::Grid};
+
+#[test]
+fn opening_characters_insert_their_closer() {
+    let mut h = Harness::new("");
+    h.type_str("f(a, [\"x");
+    assert_eq!(h.text(), "f(a, [\"x\"])");
+    assert_eq!(h.cursor(), (0, 8));
+    // closers are typed over
+    h.type_str("\"])");
+    assert_eq!(h.text(), "f(a, [\"x\"])");
+    assert_eq!(h.cursor(), (0, 11));
+}
+
+#[test]
+fn no_pair_before_a_word_or_quote_after_a_word() {
+    let mut h = Harness::new("x");
+    h.type_str("(");
+    assert_eq!(h.text(), "(x");
+    let mut h = Harness::new("");
+    h.type_str("don\"");
+    assert_eq!(h.text(), "don\"");
+}
+
+#[test]
+fn auto_pairs_can_be_turned_off() {
+    let mut h = Harness::new("");
+    h.config.auto_pairs.clear();
+    h.type_str("{");
+    assert_eq!(h.text(), "{");
+}
+
+#[test]
+fn backspace_in_empty_pair_removes_both() {
+    let mut h = Harness::new("");
+    h.type_str("a[");
+    h.press(key(KeyCode::Backspace));
+    assert_eq!(h.text(), "a");
+    assert_eq!(h.cursor(), (0, 1));
+}
+
+#[test]
+fn enter_between_braces_opens_a_block() {
+    let mut h = Harness::new("");
+    h.data.path = "main.rs".into();
+    h.type_str("fn x() {\n");
+    assert_eq!(h.text(), "fn x() {\n    \n}");
+    assert_eq!(h.cursor(), (1, 4));
+}
+
+#[test]
+fn matching_bracket_is_highlighted_and_jumped_to() {
+    let mut h = Harness::new("a(b[c]\n  d)");
+    h.press(key(KeyCode::Right));
+    assert_eq!(
+        h.data.bracket_pair().map(|(a, b)| (a.0, a.1, b.0, b.1)),
+        Some((0, 1, 1, 3))
+    );
+    let mut grid = Grid::new(40, 10);
+    render::draw(&h.data, &h.config, &mut grid);
+    assert_eq!(grid.row(1)[6].style, render::bracket_style());
+    assert_eq!(grid.row(2)[8].style, render::bracket_style());
+
+    h.press(ctrl(KeyCode::Char(']')));
+    assert_eq!(h.cursor(), (1, 3));
+    h.press(ctrl(KeyCode::Char(']')));
+    assert_eq!(h.cursor(), (0, 1));
+}
@@ -0,0 +1,103 @@
+mod harness;
+
+use crossterm::event::KeyCode;
+use harness::{ctrl, key, shift, temp_dir, Harness};
+use text_editor::ex::{complete, parse, ExCommand};
+
+/// open the command line, type `line` and run it
+fn run(h: &mut Harness, line: &str) {
+    h.press(ctrl(KeyCode::Char('g')));
+    h.type_str(line).press(key(KeyCode::Enter));
+}
+
+#[test]
+fn parses_commands_and_arguments() {
+    assert!(matches!(parse("w"), Ok(ExCommand::Write(None))));
+    assert!(matches!(
+        parse("write a b.txt"),
+        Ok(ExCommand::Write(Some(p))) if p.ends_with("a b.txt")
+    ));
+    assert!(matches!(parse(" 12 "), Ok(ExCommand::Goto(12))));
+    assert!(matches!(
+        parse("set tab_size=2"),
+        Ok(ExCommand::Set(n, Some(v))) if n == "tab_size" && v == "2"
+    ));
+    assert!(matches!(parse("s#a/b#c#g"), Ok(ExCommand::Substitute { global: true, .. })));
+    assert!(matches!(parse("!  ls -l"), Ok(ExCommand::Shell(c)) if c == "ls -l"));
+    assert_eq!(parse("frob").unwrap_err().to_string(), "unknown command: frob");
+    assert_eq!(parse("goto x").unwrap_err().to_string(), "not a line number: x");
+    assert_eq!(parse("s/a/b/q").unwrap_err().to_string(), "unknown flag: q");
+    assert!(parse("s/(/x/").is_err());
+}
+
+#[test]
+fn substitute_on_the_cursor_line_or_the_selection() {
+    let mut h = Harness::new("foo foo\nfoo\nfoo");
+    run(&mut h, "s/fo(o)/b$1/");
+    assert_eq!(h.text(), "bo foo\nfoo\nfoo");
+    h.keys([key(KeyCode::Home), shift(KeyCode::Down), shift(KeyCode::End)]);
+    run(&mut h, "s/foo/x/g");
+    assert_eq!(h.text(), "bo x\nx\nfoo");
+    assert_eq!(h.data.message.show(), Some("Replaced 2 occurrences"));
+    h.press(ctrl(KeyCode::Char('z')));
+    assert_eq!(h.text(), "bo foo\nfoo\nfoo");
+}
+
+#[test]
+fn goto_sort_and_set() {
+    let mut h = Harness::new("c\na\nb");
+    run(&mut h, "goto 2");
+    assert_eq!(h.cursor(), (2, 0));
+    run(&mut h, "sort");
+    assert_eq!(h.text(), "a\nb\nc");
+    run(&mut h, "set tab_size=2");
+    assert_eq!(h.config.tab_size, 2);
+    run(&mut h, "set tab_size");
+    assert_eq!(h.data.message.show(), Some("tab_size=2"));
+    run(&mut h, "set tab_size=0");
+    assert_eq!(h.data.message.show(), Some("invalid value for tab_size: 0"));
+}
+
+#[test]
+fn shell_filters_the_selected_lines() {
+    let mut h = Harness::new("a\nb\nz");
+    h.keys([shift(KeyCode::Down), shift(KeyCode::End)]);
+    run(&mut h, "! sort -r");
+    assert_eq!(h.text(), "b\na\nz");
+    // the cursor stays on the last filtered line
+    h.keys([shift(KeyCode::Down)]);
+    run(&mut h, "!tr a-z A-Z");
+    assert_eq!(h.text(), "b\nA\nZ");
+    run(&mut h, "!echo hi");
+    assert_eq!(h.data.message.show(), Some("hi"));
+}
+
+#[test]
+fn up_and_down_recall_earlier_lines() {
+    let mut h = Harness::new("a\nb\nc");
+    run(&mut h, "goto 1");
+    run(&mut h, "goto 2");
+    h.press(ctrl(KeyCode::Char('g')));
+    h.type_str("go");
+    h.press(key(KeyCode::Up));
+    assert_eq!(h.data.prompt.as_ref().unwrap().data.data, "goto 2");
+    h.keys([key(KeyCode::Up), key(KeyCode::Up)]);
+    assert_eq!(h.data.prompt.as_ref().unwrap().data.data, "goto 1");
+    h.keys([key(KeyCode::Down), key(KeyCode::Down)]);
+    assert_eq!(h.data.prompt.as_ref().unwrap().data.data, "go");
+}
+
+#[test]
+fn tab_completes_names_and_paths() {
+    assert_eq!(complete("go").as_deref(), Some("goto "));
+    assert_eq!(complete("s").as_deref(), Some("s"));
+    assert_eq!(complete("x"), None);
+    let dir = temp_dir("complete");
+    std::fs::create_dir(dir.join("subdir")).unwrap();
+    std::fs::write(dir.join("notes.txt"), "").unwrap();
+    let base = dir.display().to_string();
+    let file = complete(&format!("e {}/no", base));
+    assert_eq!(file, Some(format!("e {}/notes.txt", base)));
+    let sub = complete(&format!("w {}/su", base));
+    assert_eq!(sub, Some(format!("w {}/subdir/", base)));
+}
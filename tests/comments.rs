@@ -0,0 +1,65 @@
+mod harness;
+
+use crossterm::event::KeyCode;
+use harness::{ctrl, key, shift, Harness};
+
+fn toggle() -> crossterm::event::KeyEvent {
+    ctrl(KeyCode::Char('/'))
+}
+
+#[test]
+fn toggles_line_comment_on_current_line() {
+    let mut h = Harness::new("    let x = 1;");
+    h.data.path = "main.rs".into();
+    h.keys([key(KeyCode::End), toggle()]);
+    assert_eq!(h.text(), "    // let x = 1;");
+    assert_eq!(h.cursor(), (0, 17));
+    h.press(toggle());
+    assert_eq!(h.text(), "    let x = 1;");
+    assert_eq!(h.cursor(), (0, 14));
+}
+
+#[test]
+fn selected_lines_align_to_shallowest_indent() {
+    let mut h = Harness::new("if x:\n    y()\n\nz");
+    h.data.path = "a.py".into();
+    h.keys([
+        shift(KeyCode::Down),
+        shift(KeyCode::Down),
+        shift(KeyCode::Down),
+        shift(KeyCode::End),
+        toggle(),
+    ]);
+    assert_eq!(h.text(), "# if x:\n#     y()\n\n# z");
+    h.keys([key(KeyCode::Up), key(KeyCode::Up), toggle()]);
+    assert_eq!(h.text(), "# if x:\n    y()\n\n# z");
+    // a block with an uncommented line is commented as a whole
+    h.keys([
+        ctrl(KeyCode::Home),
+        shift(KeyCode::Down),
+        shift(KeyCode::End),
+        toggle(),
+    ]);
+    assert_eq!(h.text(), "# # if x:\n#     y()\n\n# z");
+}
+
+#[test]
+fn block_comment_fallback_and_undo() {
+    let mut h = Harness::new("  <p>hi</p>");
+    h.data.path = "index.html".into();
+    h.press(toggle());
+    assert_eq!(h.text(), "  <!-- <p>hi</p> -->");
+    h.press(toggle());
+    assert_eq!(h.text(), "  <p>hi</p>");
+    h.press(ctrl(KeyCode::Char('z')));
+    assert_eq!(h.text(), "  <!-- <p>hi</p> -->");
+}
+
+#[test]
+fn no_comment_syntax_leaves_text() {
+    let mut h = Harness::new("{}");
+    h.data.path = "a.json".into();
+    h.press(toggle());
+    assert_eq!(h.text(), "{}");
+    assert_eq!(h.data.message.text, "JSON has no comments");
+}
@@ -0,0 +1,85 @@
+mod harness;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use harness::{ctrl, key, Harness};
+
+fn ctrl_alt(code: KeyCode) -> KeyEvent {
+    KeyEvent::new(code, KeyModifiers::CONTROL | KeyModifiers::ALT)
+}
+
+#[test]
+fn ctrl_d_selects_word_then_adds_next_occurrences() {
+    let mut h = Harness::new("let foo = 1;\nfoo += foo;\nfood");
+    h.press(ctrl(KeyCode::Char('d')));
+    // the first press only selects the word at the cursor
+    assert_eq!(h.cursors(), [(0, 3)]);
+    h.keys([ctrl(KeyCode::Right), ctrl(KeyCode::Char('d'))]);
+    assert_eq!(h.data.selection().map(|(s, e)| (s.1, e.1)), Some((4, 7)));
+    h.keys([ctrl(KeyCode::Char('d')), ctrl(KeyCode::Char('d'))]);
+    assert_eq!(h.cursors(), [(0, 7), (1, 3), (1, 10)]);
+    h.type_str("bar");
+    assert_eq!(h.text(), "let bar = 1;\nbar += bar;\nfood");
+    assert_eq!(h.cursors(), [(0, 7), (1, 3), (1, 10)]);
+    h.press(ctrl(KeyCode::Char('z')));
+    assert_eq!(h.text(), "let foo = 1;\nfoo += foo;\nfood");
+}
+
+#[test]
+fn ctrl_alt_arrows_add_cursors_and_edit_at_all() {
+    let mut h = Harness::new("ab\nab\nab\n");
+    h.keys([
+        key(KeyCode::Right),
+        ctrl_alt(KeyCode::Down),
+        ctrl_alt(KeyCode::Down),
+    ]);
+    assert_eq!(h.cursors(), [(0, 1), (1, 1), (2, 1)]);
+    h.press(key(KeyCode::Enter));
+    assert_eq!(h.text(), "a\nb\na\nb\na\nb");
+    assert_eq!(h.cursors(), [(1, 0), (3, 0), (5, 0)]);
+    h.press(key(KeyCode::Backspace));
+    assert_eq!(h.text(), "ab\nab\nab");
+    h.keys([key(KeyCode::End), key(KeyCode::Char('!'))]);
+    assert_eq!(h.text(), "ab!\nab!\nab!");
+    // one undo step for all cursors
+    h.press(ctrl(KeyCode::Char('z')));
+    assert_eq!(h.text(), "ab\nab\nab");
+    h.press(key(KeyCode::Esc));
+    assert_eq!(h.cursors().len(), 1);
+}
+
+#[test]
+fn alt_click_adds_cursor_and_click_moves() {
+    let mut h = Harness::new("one\ntwo\nthree");
+    // row 0 is the status line, the text starts after the gutter
+    h.click(2, 7, KeyModifiers::NONE);
+    assert_eq!(h.cursor(), (1, 2));
+    h.click(3, 30, KeyModifiers::ALT);
+    assert_eq!(h.cursors(), [(1, 2), (2, 5)]);
+    h.press(key(KeyCode::Delete));
+    assert_eq!(h.text(), "one\ntw\nthree");
+    h.click(1, 5, KeyModifiers::NONE);
+    assert_eq!(h.cursors(), [(0, 0)]);
+}
+
+#[test]
+fn extra_cursors_are_drawn() {
+    let mut h = Harness::new("abc\nabc");
+    h.press(ctrl_alt(KeyCode::Down));
+    let mut grid = text_editor::screen::Grid::new(40, 10);
+    text_editor::render::draw(&h.data, &h.config, &mut grid);
+    assert_eq!(grid.row(1)[5].style, text_editor::render::extra_cursor_style());
+    assert_eq!(grid.cursor, Some((2, 5)));
+}
+
+#[test]
+fn cursor_off_screen_to_the_right_moves_with_the_rest() {
+    let mut h = Harness::new(&format!("ab\n{}", "x".repeat(100)));
+    h.keys([
+        key(KeyCode::Down),
+        ctrl_alt(KeyCode::Up),
+        key(KeyCode::End),
+        key(KeyCode::Left),
+        key(KeyCode::Right),
+    ]);
+    assert_eq!(h.cursors(), [(0, 2), (1, 100)]);
+}
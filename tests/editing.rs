@@ -0,0 +1,256 @@
+mod harness;
+
+use crossterm::event::{KeyCode, KeyModifiers};
+use harness::{ctrl, key, Harness};
+use text_editor::{cmdline::Config, editor::Outcome};
+
+#[test]
+fn backspace_joins_with_previous_line() {
+    let mut h = Harness::new("ab\ncd");
+    h.keys([key(KeyCode::Down), key(KeyCode::Backspace)]);
+    assert_eq!(h.text(), "abcd");
+    assert_eq!(h.cursor(), (0, 2));
+}
+
+#[test]
+fn backspace_removes_multibyte_char() {
+    let mut h = Harness::new("zół");
+    h.keys([key(KeyCode::End), key(KeyCode::Backspace)]);
+    assert_eq!(h.text(), "zó");
+    assert_eq!(h.cursor(), (0, 2));
+}
+
+#[test]
+fn enter_splits_line_at_cursor() {
+    let mut h = Harness::new("hello");
+    h.keys([key(KeyCode::Right), key(KeyCode::Right), key(KeyCode::Enter)]);
+    assert_eq!(h.text(), "he\nllo");
+    assert_eq!(h.cursor(), (1, 0));
+}
+
+#[test]
+fn delete_removes_next_char_and_joins_lines() {
+    let mut h = Harness::new("ab\ncd");
+    h.keys([key(KeyCode::Delete)]);
+    assert_eq!(h.text(), "b\ncd");
+    h.keys([key(KeyCode::End), key(KeyCode::Delete)]);
+    assert_eq!(h.text(), "bcd");
+    assert_eq!(h.cursor(), (0, 1));
+}
+
+#[test]
+fn tab_and_back_tab() {
+    let mut h = Harness::new("x");
+    h.keys([key(KeyCode::Tab)]);
+    assert_eq!(h.text(), "    x");
+    assert_eq!(h.cursor(), (0, 4));
+    h.keys([key(KeyCode::BackTab)]);
+    assert_eq!(h.text(), "x");
+    assert_eq!(h.cursor(), (0, 0));
+}
+
+#[test]
+fn typing_inserts_at_cursor() {
+    let mut h = Harness::new("");
+    h.type_str("fn ż()\n}");
+    assert_eq!(h.text(), "fn ż()\n}");
+    assert_eq!(h.cursor(), (1, 1));
+}
+
+#[test]
+fn right_at_end_of_line_goes_to_next_line() {
+    let mut h = Harness::new("a\nb");
+    h.keys([key(KeyCode::End), key(KeyCode::Right)]);
+    assert_eq!(h.cursor(), (1, 0));
+}
+
+#[test]
+fn search_finds_next_match_and_wraps() {
+    let mut h = Harness::new("one\ntwo\nthree two");
+    h.keys([key(KeyCode::Down), key(KeyCode::Right), ctrl(KeyCode::Char('f'))]);
+    h.type_str("two").keys([key(KeyCode::Enter)]);
+    assert_eq!(h.cursor(), (2, 6));
+    assert!(h.data.prompt.is_none());
+
+    h.keys([ctrl(KeyCode::Char('f'))]);
+    h.type_str("one").keys([key(KeyCode::Enter)]);
+    assert_eq!(h.cursor(), (0, 0));
+}
+
+#[test]
+fn search_reports_missing_phrase() {
+    let mut h = Harness::new("abc");
+    h.keys([ctrl(KeyCode::Char('f'))]);
+    h.type_str("zzz").keys([key(KeyCode::Enter)]);
+    assert_eq!(h.cursor(), (0, 0));
+    assert_eq!(h.data.message.text, "Phrase: \"zzz\" not found");
+}
+
+#[test]
+fn escape_cancels_prompt() {
+    let mut h = Harness::new("abc");
+    h.keys([ctrl(KeyCode::Char('f'))]);
+    h.type_str("b").keys([key(KeyCode::Esc)]);
+    assert!(h.data.prompt.is_none());
+    assert_eq!(h.text(), "abc");
+}
+
+#[test]
+fn frame_shows_gutter_text_and_cursor() {
+    let mut h = Harness::with_size("first\nsecond", 6, 30);
+    h.keys([key(KeyCode::Down), key(KeyCode::End)]);
+    let frame = h.frame();
+    assert_eq!(frame[1], "000| first");
+    assert_eq!(frame[2], "001| second");
+    assert_eq!(frame[3], "~");
+    assert_eq!(frame[5], "Ctrl+Q: quit, F1: commands");
+    assert_eq!(h.screen_cursor(), Some((2, 11)));
+}
+
+#[test]
+fn frame_shows_open_prompt() {
+    let mut h = Harness::with_size("text", 6, 30);
+    h.keys([ctrl(KeyCode::Char('f'))]);
+    h.type_str("ex");
+    assert_eq!(h.frame()[5], "Search:  ex");
+}
+
+#[test]
+fn quit_asks_again_when_edited() {
+    let mut h = Harness::new("");
+    assert_eq!(h.press(ctrl(KeyCode::Char('q'))), Outcome::Quit);
+
+    let mut h = Harness::new("");
+    h.type_str("x");
+    assert_eq!(h.press(ctrl(KeyCode::Char('q'))), Outcome::Continue);
+    assert_eq!(h.press(ctrl(KeyCode::Char('q'))), Outcome::Quit);
+}
+
+#[test]
+fn ctrl_arrows_move_by_word() {
+    let mut h = Harness::new("let foo_bar = a.b;\nnext");
+    h.press(ctrl(KeyCode::Right));
+    assert_eq!(h.cursor(), (0, 3));
+    h.press(ctrl(KeyCode::Right));
+    assert_eq!(h.cursor(), (0, 11));
+    h.keys([ctrl(KeyCode::Right), ctrl(KeyCode::Right)]);
+    assert_eq!(h.cursor(), (0, 15));
+    h.press(ctrl(KeyCode::Left));
+    assert_eq!(h.cursor(), (0, 14));
+    h.keys([key(KeyCode::End), ctrl(KeyCode::Right)]);
+    assert_eq!(h.cursor(), (1, 0));
+    h.press(ctrl(KeyCode::Left));
+    assert_eq!(h.cursor(), (0, 18));
+}
+
+#[test]
+fn word_chars_are_configurable() {
+    let mut h = Harness::new("foo-bar baz");
+    h.press(ctrl(KeyCode::Right));
+    assert_eq!(h.cursor(), (0, 3));
+    h.config.word_chars = "_-".to_owned();
+    h.keys([key(KeyCode::Home), ctrl(KeyCode::Right)]);
+    assert_eq!(h.cursor(), (0, 7));
+}
+
+#[test]
+fn ctrl_backspace_and_delete_remove_words() {
+    let mut h = Harness::new("zażółć gęślą jaźń");
+    h.keys([key(KeyCode::End), ctrl(KeyCode::Backspace)]);
+    assert_eq!(h.text(), "zażółć gęślą ");
+    assert_eq!(h.cursor(), (0, 13));
+    h.keys([key(KeyCode::Home), ctrl(KeyCode::Delete)]);
+    assert_eq!(h.text(), " gęślą ");
+    assert_eq!(h.cursor(), (0, 0));
+    h.press(ctrl(KeyCode::Backspace));
+    assert_eq!(h.text(), " gęślą ");
+}
+
+#[test]
+fn enter_keeps_indentation() {
+    let mut h = Harness::new("    let x = 1;");
+    h.keys([key(KeyCode::End), key(KeyCode::Enter)]);
+    assert_eq!(h.text(), "    let x = 1;\n    ");
+    assert_eq!(h.cursor(), (1, 4));
+    // plain text has no extra indent after a brace
+    h.type_str("{\n");
+    assert_eq!(h.cursor(), (2, 4));
+}
+
+#[test]
+fn enter_indents_after_opening_per_language() {
+    let mut h = Harness::new("fn main() {\nif x:");
+    h.data.path = "main.rs".into();
+    h.keys([key(KeyCode::End), key(KeyCode::Enter)]);
+    assert_eq!(h.cursor(), (1, 4));
+    h.keys([key(KeyCode::Down), key(KeyCode::End), key(KeyCode::Enter)]);
+    assert_eq!(h.cursor(), (3, 0));
+
+    let mut h = Harness::new("if x:");
+    h.data.path = "script.py".into();
+    h.keys([key(KeyCode::End), key(KeyCode::Enter)]);
+    assert_eq!(h.text(), "if x:\n    ");
+}
+
+#[test]
+fn enter_moves_rest_of_line_to_new_indent() {
+    let mut h = Harness::new("  call(a,   b)");
+    h.data.path = "a.c".into();
+    for _ in 0..9 {
+        h.press(key(KeyCode::Right));
+    }
+    h.press(key(KeyCode::Enter));
+    assert_eq!(h.text(), "  call(a,\n  b)");
+    assert_eq!(h.cursor(), (1, 2));
+}
+
+#[test]
+fn tab_aligns_to_tab_stop() {
+    let mut h = Harness::new("ab");
+    h.keys([key(KeyCode::End), key(KeyCode::Tab)]);
+    assert_eq!(h.text(), "ab  ");
+    assert_eq!(h.cursor(), (0, 4));
+    h.press(key(KeyCode::Tab));
+    assert_eq!(h.cursor(), (0, 8));
+}
+
+#[test]
+fn tabs_are_drawn_up_to_the_next_tab_stop() {
+    let config = Config {
+        use_tabs: true,
+        ..Config::default()
+    };
+    let mut h = Harness::with_config("\tab\n\t\tc", config);
+    let frame = h.frame();
+    assert_eq!(frame[1], "000|     ab");
+    assert_eq!(frame[2], "001|         c");
+    h.keys([key(KeyCode::Down), key(KeyCode::End)]);
+    assert_eq!(h.screen_cursor(), Some((2, 14)));
+    // Up keeps the screen column, not the character count
+    h.press(key(KeyCode::Up));
+    assert_eq!(h.cursor(), (0, 3));
+    assert_eq!(h.screen_cursor(), Some((1, 11)));
+    // a click inside a tab lands before it
+    h.click(2, 11, KeyModifiers::NONE);
+    assert_eq!(h.cursor(), (1, 1));
+    h.config.use_tabs = false;
+    h.keys([key(KeyCode::Up), key(KeyCode::End), key(KeyCode::Tab)]);
+    assert_eq!(h.text(), "\tab  \n\t\tc");
+    assert_eq!(h.screen_cursor(), Some((1, 13)));
+}
+
+#[test]
+fn backspace_in_indentation_removes_one_level() {
+    let mut h = Harness::new("          x");
+    for _ in 0..10 {
+        h.press(key(KeyCode::Right));
+    }
+    h.press(key(KeyCode::Backspace));
+    assert_eq!(h.text(), "        x");
+    h.press(key(KeyCode::Backspace));
+    assert_eq!(h.text(), "    x");
+    assert_eq!(h.cursor(), (0, 4));
+    // after text Backspace removes a single character again
+    h.keys([key(KeyCode::End), key(KeyCode::Backspace)]);
+    assert_eq!(h.text(), "    ");
+}
@@ -0,0 +1,83 @@
+mod harness;
+
+use encoding_rs::{UTF_16BE, UTF_16LE, UTF_8, WINDOWS_1252};
+use harness::temp_dir;
+use text_editor::{
+    cmdline::Config,
+    data::{FileData, TextPos},
+    encoding::{decode_as, detect, encode, Decoded, FileEncoding},
+    error::Error,
+};
+
+fn text(d: Decoded) -> (String, FileEncoding) {
+    match d {
+        Decoded::Text(t, e) => (t, e),
+        Decoded::Binary(..) => panic!("detected as binary"),
+    }
+}
+
+#[test]
+fn utf8_without_bom() {
+    let (t, e) = text(detect("zażółć\n".as_bytes(), WINDOWS_1252));
+    assert_eq!(t, "zażółć\n");
+    assert_eq!(e, FileEncoding::default());
+}
+
+#[test]
+fn utf16_with_and_without_bom_round_trips() {
+    for (encoding, bom) in [(UTF_16LE, true), (UTF_16BE, true), (UTF_16LE, false)] {
+        let enc = FileEncoding { encoding, bom };
+        let bytes = encode("żółw\r\nabc\r\n", enc).unwrap();
+        let (t, e) = text(detect(&bytes, WINDOWS_1252));
+        assert_eq!(t, "żółw\r\nabc\r\n");
+        assert_eq!(e, enc);
+    }
+}
+
+#[test]
+fn invalid_utf8_falls_back_to_legacy_encoding() {
+    let bytes = b"caf\xe9\n";
+    let (t, e) = text(detect(bytes, WINDOWS_1252));
+    assert_eq!(t, "café\n");
+    assert_eq!(e.encoding, WINDOWS_1252);
+    assert_eq!(encode(&t, e).unwrap(), bytes);
+}
+
+#[test]
+fn unencodable_character_is_reported() {
+    let enc = FileEncoding {
+        encoding: WINDOWS_1252,
+        bom: false,
+    };
+    assert_eq!(encode("aż", enc), Err('ż'));
+}
+
+#[test]
+fn nul_bytes_mean_binary() {
+    assert!(matches!(
+        detect(b"\x7fELF\x02\x01\x01\x00\x00\x00\x00\x00\x00\x00\x00\x00\x03\x00>", UTF_8),
+        Decoded::Binary(..)
+    ));
+}
+
+#[test]
+fn reopened_binary_file_stays_read_only() {
+    let dir = temp_dir("reopen-binary");
+    let path = dir.join("a.out");
+    let bytes = b"\x7fELF\x00\x01\xff";
+    std::fs::write(&path, bytes).unwrap();
+    let config = Config::default();
+    let mut data = FileData::from_path(&path, config.clone(), TextPos(10, 40)).unwrap();
+    assert!(data.read_only);
+    data.reopen(WINDOWS_1252, &config).unwrap();
+    assert!(data.read_only);
+    assert!(matches!(data.save(), Err(Error::ReadOnly(_))));
+    assert_eq!(std::fs::read(&path).unwrap(), bytes);
+}
+
+#[test]
+fn decode_as_skips_matching_bom() {
+    let (t, e) = decode_as(b"\xef\xbb\xbfabc", UTF_8);
+    assert_eq!(t, "abc");
+    assert!(e.bom);
+}
@@ -0,0 +1,120 @@
+mod harness;
+
+use std::fs;
+
+use crossterm::event::{KeyCode, KeyModifiers};
+use harness::{ctrl, key, temp_dir, Harness, TempDir};
+use text_editor::explorer::{Explorer, EXPLORER_WIDTH};
+
+/// a project with a folder, two files and an ignored log, shown in the explorer
+fn project(name: &str) -> (Harness, TempDir) {
+    let dir = temp_dir(name);
+    fs::create_dir(dir.join("src")).unwrap();
+    fs::write(dir.join("src/main.rs"), "fn main() {}").unwrap();
+    fs::write(dir.join("notes.txt"), "notes").unwrap();
+    fs::write(dir.join("debug.log"), "").unwrap();
+    fs::write(dir.join(".gitignore"), "*.log\n").unwrap();
+    let mut h = Harness::with_size("scratch", 10, 60);
+    h.data.explorer = Some(Explorer::new(dir.to_path_buf()));
+    (h, dir)
+}
+
+fn names(h: &Harness) -> Vec<String> {
+    let e = h.data.explorer.as_ref().unwrap();
+    e.entries.iter().map(|e| e.name()).collect()
+}
+
+#[test]
+fn folders_expand_and_ignored_files_are_hidden() {
+    let (mut h, _dir) = project("explorer-tree");
+    assert_eq!(names(&h), ["src", ".gitignore", "notes.txt"]);
+    h.press(key(KeyCode::Right));
+    assert_eq!(names(&h), ["src", "main.rs", ".gitignore", "notes.txt"]);
+    let frame = h.frame();
+    assert!(frame[1].starts_with("▾ src"), "{:?}", frame[1]);
+    assert!(frame[2].starts_with("    main.rs"), "{:?}", frame[2]);
+    // Left on a file goes up to its folder, then closes it
+    h.keys([key(KeyCode::Down), key(KeyCode::Left)]);
+    assert_eq!(h.data.explorer.as_ref().unwrap().selected, 0);
+    h.press(key(KeyCode::Left));
+    assert_eq!(names(&h).len(), 3);
+    h.press(key(KeyCode::Char('i')));
+    assert_eq!(names(&h), ["src", ".gitignore", "debug.log", "notes.txt"]);
+}
+
+#[test]
+fn text_moves_right_of_the_pane() {
+    let (mut h, _dir) = project("explorer-render");
+    let frame = h.frame();
+    let text: String = frame[1].chars().skip(EXPLORER_WIDTH).collect();
+    assert_eq!(text, "000| scratch");
+    // typing goes to the tree until the focus goes back to the text
+    h.type_str("x");
+    assert_eq!(h.text(), "scratch");
+    h.press(key(KeyCode::Esc));
+    h.type_str("x");
+    assert_eq!(h.text(), "xscratch");
+    assert_eq!(h.screen_cursor(), Some((1, EXPLORER_WIDTH + 6)));
+    // Ctrl+B focuses the tree, then hides it
+    h.keys([ctrl(KeyCode::Char('b')), ctrl(KeyCode::Char('b'))]);
+    assert_eq!(h.frame()[1], "000| xscratch");
+}
+
+#[test]
+fn enter_opens_files_in_buffers_of_their_own() {
+    let (mut h, dir) = project("explorer-open");
+    h.keys([key(KeyCode::End), key(KeyCode::Enter)]);
+    assert_eq!(h.text(), "notes");
+    assert_eq!(h.data.path, dir.join("notes.txt"));
+    assert!(!h.data.explorer.as_ref().unwrap().focused);
+    assert_eq!(h.data.buffers.len(), 1);
+    h.press(ctrl(KeyCode::Char('b')));
+    h.keys([key(KeyCode::Home), key(KeyCode::Right), key(KeyCode::Down)]);
+    h.press(key(KeyCode::Enter));
+    assert_eq!(h.text(), "fn main() {}");
+    h.press(ctrl(KeyCode::PageUp));
+    assert_eq!(h.text(), "notes");
+    h.press(ctrl(KeyCode::PageUp));
+    assert_eq!(h.text(), "scratch");
+    h.press(ctrl(KeyCode::PageUp));
+    assert_eq!(h.text(), "fn main() {}");
+    // an open file is switched to, not opened twice
+    h.press(ctrl(KeyCode::Char('b')));
+    h.keys([key(KeyCode::End), key(KeyCode::Enter)]);
+    assert_eq!(h.text(), "notes");
+    assert_eq!(h.data.buffers.len(), 2);
+    // the explorer pane keeps its clicks
+    h.click(2, 2, KeyModifiers::NONE);
+    assert_eq!(h.text(), "fn main() {}");
+}
+
+#[test]
+fn files_are_created_renamed_and_deleted_after_asking() {
+    let (mut h, dir) = project("explorer-files");
+    // new files go next to the selected file or into the selected folder
+    h.keys([key(KeyCode::End), key(KeyCode::Char('n'))]);
+    h.type_str("docs/todo.txt");
+    h.press(key(KeyCode::Enter));
+    assert!(dir.join("docs/todo.txt").is_file());
+    assert_eq!(h.data.explorer.as_ref().unwrap().selected, 0);
+    h.press(key(KeyCode::End));
+    h.press(key(KeyCode::Char('r')));
+    assert_eq!(h.data.prompt.as_ref().unwrap().data.data, "notes.txt");
+    h.press(ctrl(KeyCode::Char('u')));
+    h.type_str(".gitignore");
+    h.press(key(KeyCode::Enter));
+    // the name is taken, no replaces nothing
+    h.press(key(KeyCode::Char('n')));
+    assert!(dir.join("notes.txt").is_file());
+    h.press(key(KeyCode::Char('r')));
+    h.press(ctrl(KeyCode::Char('u')));
+    h.type_str("todo.md");
+    h.press(key(KeyCode::Enter));
+    assert!(dir.join("todo.md").is_file());
+    assert_eq!(names(&h)[h.data.explorer.as_ref().unwrap().selected], "todo.md");
+    h.press(key(KeyCode::Char('d')));
+    assert!(dir.join("todo.md").is_file());
+    h.press(key(KeyCode::Char('y')));
+    assert!(!dir.join("todo.md").exists());
+    assert_eq!(names(&h), ["docs", "src", ".gitignore"]);
+}
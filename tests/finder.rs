@@ -0,0 +1,64 @@
+mod harness;
+
+use std::{fs, time::Duration};
+
+use crossterm::event::KeyCode;
+use harness::{ctrl, key, temp_repository, Harness, TempDir};
+use text_editor::finder;
+
+/// a repository with sources, a build folder it ignores and a notes file
+fn project(name: &str) -> TempDir {
+    let dir = temp_repository(name);
+    fs::create_dir_all(dir.join("src/deep")).unwrap();
+    fs::write(dir.join("src/main.rs"), "fn main() {\n    run();\n}").unwrap();
+    fs::write(dir.join("src/deep/mine.rs"), "// mine").unwrap();
+    fs::write(dir.join("target/main.o"), "").unwrap();
+    fs::write(dir.join("notes.txt"), "notes").unwrap();
+    dir
+}
+
+fn labels(h: &Harness) -> Vec<String> {
+    let list = h.data.prompt.as_ref().unwrap().list.as_ref().unwrap();
+    list.shown.iter().map(|(i, _)| list.items[*i].label.clone()).collect()
+}
+
+#[test]
+fn the_root_is_the_folder_holding_git() {
+    let dir = project("finder-root");
+    assert_eq!(finder::project_root(&dir.join("src/deep")), *dir);
+}
+
+#[test]
+fn ctrl_p_ranks_project_files_and_opens_the_pick() {
+    let dir = project("finder-pick");
+    let mut h = Harness::with_size("", 20, 60);
+    h.press(ctrl(KeyCode::Char('g')));
+    h.type_str(&format!("e {}", dir.join("src/deep/mine.rs").display()));
+    h.press(key(KeyCode::Enter));
+    h.press(ctrl(KeyCode::Char('p')));
+    let mut all = labels(&h);
+    all.sort();
+    assert_eq!(all, [".gitignore", "notes.txt", "src/deep/mine.rs", "src/main.rs"]);
+    h.type_str("mn.rs");
+    assert_eq!(labels(&h)[0], "src/main.rs");
+    let frame = h.frame();
+    assert!(frame[1].contains("src/main.rs"), "{:?}", frame[1]);
+    assert_eq!(frame[2], "fn main() {");
+    h.press(key(KeyCode::Enter));
+    assert_eq!(h.text(), "fn main() {\n    run();\n}");
+    assert_eq!(h.data.path, dir.join("src/main.rs"));
+    assert_eq!(h.data.buffers.len(), 1);
+}
+
+#[tokio::test]
+async fn indexing_runs_on_a_task() {
+    let dir = project("finder-task");
+    let mut index = finder::index(dir.to_path_buf());
+    let mut files = Vec::new();
+    while !index.done() {
+        files.append(&mut index.take_new());
+        tokio::time::sleep(Duration::from_millis(5)).await;
+    }
+    files.sort();
+    assert_eq!(files, [".gitignore", "notes.txt", "src/deep/mine.rs", "src/main.rs"]);
+}
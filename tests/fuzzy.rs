@@ -0,0 +1,23 @@
+use text_editor::fuzzy::score;
+
+#[test]
+fn characters_must_appear_in_order() {
+    assert_eq!(score("abc", "a_b_c").map(|s| s.1), Some(vec![0, 2, 4]));
+    assert_eq!(score("ABC", "abc").map(|s| s.1), Some(vec![0, 1, 2]));
+    assert_eq!(score("cba", "abc"), None);
+    assert_eq!(score("", "abc"), Some((0, vec![])));
+}
+
+#[test]
+fn word_starts_and_runs_score_higher() {
+    let (word, cols) = score("ml", "move lines").unwrap();
+    assert_eq!(cols, vec![0, 5]);
+    assert!(word > score("ml", "small").unwrap().0);
+    assert!(score("line", "Join lines").unwrap().0 > score("line", "l_i_n_e").unwrap().0);
+}
+
+#[test]
+fn prefers_a_later_word_start_only_when_the_rest_still_fits() {
+    assert_eq!(score("ab", "xab_a").map(|s| s.1), Some(vec![1, 2]));
+    assert_eq!(score("b", "ab_b").map(|s| s.1), Some(vec![3]));
+}
@@ -0,0 +1,102 @@
+mod harness;
+
+use std::fs;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use harness::{ctrl, key, temp_repository, Harness, TempDir};
+
+/// a repository with two sources using `old`, an ignored copy and a binary file
+fn project(name: &str) -> TempDir {
+    let dir = temp_repository(name);
+    fs::create_dir(dir.join("src")).unwrap();
+    fs::write(dir.join("src/a.rs"), "fn a() {\n    old(old);\n}\n").unwrap();
+    fs::write(dir.join("src/b.rs"), "// old\n").unwrap();
+    fs::write(dir.join("target/a.rs"), "old\n").unwrap();
+    fs::write(dir.join("data.bin"), b"old\0").unwrap();
+    dir
+}
+
+/// a harness editing a file of the project, so the project is where it searches
+fn in_project(dir: &std::path::Path) -> Harness {
+    let mut h = Harness::with_size("", 12, 80);
+    h.press(ctrl(KeyCode::Char('g')));
+    h.type_str(&format!("e {}", dir.join("src/b.rs").display()));
+    h.press(key(KeyCode::Enter));
+    h
+}
+
+fn sorted_results(h: &Harness) -> Vec<String> {
+    let mut lines: Vec<String> = h.text().lines().skip(1).map(str::to_owned).collect();
+    lines.sort();
+    lines
+}
+
+#[test]
+fn grep_lists_matches_and_enter_opens_them() {
+    let dir = project("grep-open");
+    let mut h = in_project(&dir);
+    let ctrl_shift = KeyModifiers::CONTROL | KeyModifiers::SHIFT;
+    h.press(KeyEvent::new(KeyCode::Char('F'), ctrl_shift));
+    h.type_str("ol+d");
+    h.press(key(KeyCode::Enter));
+    assert!(h.data.read_only);
+    assert!(h.text().starts_with("grep /ol+d/: 2 lines in 2 files, Enter: open"));
+    assert_eq!(sorted_results(&h), ["src/a.rs:1:     old(old);", "src/b.rs:0: // old"]);
+    let row = h.text().lines().position(|l| l.starts_with("src/a.rs")).unwrap();
+    for _ in 0..row {
+        h.press(key(KeyCode::Down));
+    }
+    h.press(key(KeyCode::Enter));
+    assert_eq!(h.data.path, dir.join("src/a.rs"));
+    assert_eq!(h.cursor(), (1, 4));
+}
+
+#[test]
+fn replace_previews_then_edits_each_buffer_as_one_step() {
+    let dir = project("grep-replace");
+    let mut h = in_project(&dir);
+    h.press(ctrl(KeyCode::Char('g')));
+    h.type_str("replace /old/new/");
+    h.press(key(KeyCode::Enter));
+    assert_eq!(
+        sorted_results(&h),
+        [
+            "src/a.rs:1: +     new(new);",
+            "src/a.rs:1: -     old(old);",
+            "src/b.rs:0: + // new",
+            "src/b.rs:0: - // old",
+        ]
+    );
+    h.press(key(KeyCode::Char('a')));
+    assert!(h.data.message.text.starts_with("Replaced 3 occurrences in 2 buffers"));
+    // the files change when their buffers are saved
+    assert_eq!(fs::read_to_string(dir.join("src/b.rs")).unwrap(), "// old\n");
+    h.press(ctrl(KeyCode::PageDown));
+    assert_eq!(h.text(), "// new");
+    h.press(ctrl(KeyCode::PageDown));
+    assert_eq!(h.text(), "fn a() {\n    new(new);\n}");
+    h.press(ctrl(KeyCode::Char('z')));
+    assert_eq!(h.text(), "fn a() {\n    old(old);\n}");
+}
+
+#[test]
+fn replace_leaves_lines_the_search_did_not_show() {
+    let dir = project("grep-edited");
+    let mut h = in_project(&dir);
+    // unsaved edits: the shown match changes and a new one is never shown
+    h.type_str("x");
+    h.press(key(KeyCode::End));
+    h.type_str("\nold too");
+    h.press(ctrl(KeyCode::Char('g')));
+    h.type_str("replace /old/new/");
+    h.press(key(KeyCode::Enter));
+    assert_eq!(sorted_results(&h).len(), 4);
+    h.press(key(KeyCode::Char('a')));
+    assert!(h.data.message.text.starts_with(
+        "Replaced 2 occurrences in 1 buffers, 1 lines changed since the search were skipped"
+    ));
+    h.press(ctrl(KeyCode::PageDown));
+    assert_eq!(h.text(), "x// old\nold too");
+    h.press(ctrl(KeyCode::PageDown));
+    assert_eq!(h.text(), "fn a() {\n    new(new);\n}");
+}
@@ -0,0 +1,190 @@
+//! Drives the editor core without a terminal: feed key events, then look at the buffer,
+//! the cursor and the rendered frame.
+#![allow(dead_code)]
+
+use std::{
+    fs,
+    ops::Deref,
+    path::{Path, PathBuf},
+};
+
+use crossterm::event::{
+    KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
+};
+use text_editor::{
+    cmdline::Config,
+    data::{FileData, TextPos},
+    editor::{handle_key, handle_mouse, Outcome},
+    render,
+    screen::Grid,
+};
+
+pub struct Harness {
+    pub data: FileData,
+    pub config: Config,
+}
+
+impl Harness {
+    /// a buffer with `text` on a 10 rows by 40 columns screen
+    pub fn new(text: &str) -> Self {
+        Self::with_size(text, 10, 40)
+    }
+    pub fn with_size(text: &str, rows: usize, cols: usize) -> Self {
+        let config = Config::default();
+        Self {
+            data: FileData::from_text(text, &config, TextPos(rows, cols)),
+            config,
+        }
+    }
+    /// a buffer with `text` loaded under `config` on a 10 rows by 40 columns screen
+    pub fn with_config(text: &str, config: Config) -> Self {
+        Self {
+            data: FileData::from_text(text, &config, TextPos(10, 40)),
+            config,
+        }
+    }
+    pub fn press(&mut self, ke: KeyEvent) -> Outcome {
+        let outcome = handle_key(ke, &mut self.data, &mut self.config);
+        self.check_cursor();
+        outcome
+    }
+    /// left click on the screen cell at `row`, `col`
+    pub fn click(&mut self, row: u16, col: u16, modifiers: KeyModifiers) {
+        let me = MouseEvent {
+            kind: MouseEventKind::Down(MouseButton::Left),
+            column: col,
+            row,
+            modifiers,
+        };
+        handle_mouse(me, &mut self.data, &mut self.config);
+        self.check_cursor();
+    }
+    /// drag with the left button held to the screen cell at `row`, `col`
+    pub fn drag(&mut self, row: u16, col: u16, modifiers: KeyModifiers) {
+        let me = MouseEvent {
+            kind: MouseEventKind::Drag(MouseButton::Left),
+            column: col,
+            row,
+            modifiers,
+        };
+        handle_mouse(me, &mut self.data, &mut self.config);
+        self.check_cursor();
+    }
+    pub fn keys(&mut self, keys: impl IntoIterator<Item = KeyEvent>) -> &mut Self {
+        for ke in keys {
+            self.press(ke);
+        }
+        self
+    }
+    /// type every character of `text`, a newline presses Enter
+    pub fn type_str(&mut self, text: &str) -> &mut Self {
+        for c in text.chars() {
+            self.press(match c {
+                '\n' => key(KeyCode::Enter),
+                c => key(KeyCode::Char(c)),
+            });
+        }
+        self
+    }
+    pub fn text(&self) -> String {
+        self.data
+            .lines
+            .iter()
+            .map(|l| l.data.as_str())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+    /// (line, character column) of the cursor
+    pub fn cursor(&self) -> (usize, usize) {
+        (self.data.cursor_location.0, self.data.cursor_location.1)
+    }
+    /// (line, character column) of every cursor in text order
+    pub fn cursors(&self) -> Vec<(usize, usize)> {
+        let mut all: Vec<_> = std::iter::once(self.data.cursor())
+            .chain(self.data.cursors.iter().copied())
+            .map(|c| (c.cursor_location.0, c.cursor_location.1))
+            .collect();
+        all.sort();
+        all
+    }
+    /// rows of the rendered screen with trailing blanks removed
+    pub fn frame(&self) -> Vec<String> {
+        let mut grid = Grid::new(self.data.size.1, self.data.size.0);
+        render::draw(&self.data, &self.config, &mut grid);
+        (0..grid.height)
+            .map(|r| grid.row_text(r).trim_end().to_owned())
+            .collect()
+    }
+    /// (row, column) of the cursor on the rendered screen
+    pub fn screen_cursor(&self) -> Option<(usize, usize)> {
+        let mut grid = Grid::new(self.data.size.1, self.data.size.0);
+        render::draw(&self.data, &self.config, &mut grid);
+        grid.cursor
+    }
+    /// the byte offset and the character column of the cursor must describe the same place
+    fn check_cursor(&self) {
+        let d = &self.data;
+        for c in std::iter::once(d.cursor()).chain(d.cursors.iter().copied()) {
+            assert_eq!(c.location.0, c.cursor_location.0, "cursor rows differ");
+            let line = &d.lines[c.location.0].data;
+            assert!(
+                line.is_char_boundary(c.location.1),
+                "byte offset {} is not a char boundary in {:?}",
+                c.location.1,
+                line
+            );
+            assert_eq!(
+                line[..c.location.1].chars().count(),
+                c.cursor_location.1,
+                "byte offset and column disagree in {:?}",
+                line
+            );
+        }
+    }
+}
+
+pub fn key(code: KeyCode) -> KeyEvent {
+    KeyEvent::new(code, KeyModifiers::NONE)
+}
+pub fn ctrl(code: KeyCode) -> KeyEvent {
+    KeyEvent::new(code, KeyModifiers::CONTROL)
+}
+pub fn shift(code: KeyCode) -> KeyEvent {
+    KeyEvent::new(code, KeyModifiers::SHIFT)
+}
+pub fn alt(code: KeyCode) -> KeyEvent {
+    KeyEvent::new(code, KeyModifiers::ALT)
+}
+
+/// A fresh directory for one test, removed again when dropped so a failing test does not
+/// leave it behind either.
+pub struct TempDir(PathBuf);
+impl Deref for TempDir {
+    type Target = Path;
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+/// an empty directory named after the test
+pub fn temp_dir(name: &str) -> TempDir {
+    let dir = std::env::temp_dir().join(format!("te-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    TempDir(dir)
+}
+
+/// a directory holding `.git`, with a `target` folder its `.gitignore` excludes
+pub fn temp_repository(name: &str) -> TempDir {
+    let dir = temp_dir(name);
+    for sub in [".git", "target"] {
+        fs::create_dir(dir.join(sub)).unwrap();
+    }
+    fs::write(dir.join(".gitignore"), "target/\n").unwrap();
+    dir
+}
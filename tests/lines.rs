@@ -0,0 +1,71 @@
+mod harness;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use harness::{alt, ctrl, key, shift, Harness};
+
+fn ctrl_shift(c: char) -> KeyEvent {
+    KeyEvent::new(KeyCode::Char(c), KeyModifiers::CONTROL | KeyModifiers::SHIFT)
+}
+
+#[test]
+fn duplicate_line_puts_cursor_on_copy() {
+    let mut h = Harness::new("a\nbc\nd");
+    h.keys([key(KeyCode::Down), key(KeyCode::Right), ctrl_shift('D')]);
+    assert_eq!(h.text(), "a\nbc\nbc\nd");
+    assert_eq!(h.cursor(), (2, 1));
+    h.press(ctrl(KeyCode::Char('z')));
+    assert_eq!(h.text(), "a\nbc\nd");
+}
+
+#[test]
+fn duplicate_selected_lines() {
+    let mut h = Harness::new("a\nb\nc");
+    h.keys([shift(KeyCode::Down), shift(KeyCode::End), ctrl_shift('d')]);
+    assert_eq!(h.text(), "a\nb\na\nb\nc");
+    assert_eq!(h.cursor(), (3, 1));
+    assert!(h.data.selection().is_some());
+}
+
+#[test]
+fn delete_lines_keeps_column() {
+    let mut h = Harness::new("abc\ndefg\nh");
+    h.keys([key(KeyCode::End), ctrl(KeyCode::Char('k'))]);
+    assert_eq!(h.text(), "defg\nh");
+    assert_eq!(h.cursor(), (0, 3));
+    h.keys([key(KeyCode::Down), ctrl(KeyCode::Char('k'))]);
+    assert_eq!(h.text(), "defg");
+    assert_eq!(h.cursor(), (0, 1));
+    h.press(ctrl(KeyCode::Char('k')));
+    assert_eq!(h.text(), "");
+}
+
+#[test]
+fn alt_arrows_move_lines_as_one_undo_step_each() {
+    let mut h = Harness::new("a\nb\nc\nd");
+    h.keys([
+        key(KeyCode::Down),
+        shift(KeyCode::Down),
+        shift(KeyCode::End),
+        alt(KeyCode::Down),
+    ]);
+    assert_eq!(h.text(), "a\nd\nb\nc");
+    assert_eq!(h.cursor(), (3, 1));
+    // already at the bottom
+    h.press(alt(KeyCode::Down));
+    assert_eq!(h.text(), "a\nd\nb\nc");
+    h.keys([alt(KeyCode::Up), alt(KeyCode::Up), alt(KeyCode::Up)]);
+    assert_eq!(h.text(), "b\nc\na\nd");
+    assert_eq!(h.cursor(), (1, 1));
+    h.press(ctrl(KeyCode::Char('z')));
+    assert_eq!(h.text(), "a\nb\nc\nd");
+}
+
+#[test]
+fn join_normalizes_whitespace() {
+    let mut h = Harness::new("let x =   \n      1 +\n 2;");
+    h.press(ctrl(KeyCode::Char('j')));
+    assert_eq!(h.text(), "let x = 1 +\n 2;");
+    assert_eq!(h.cursor(), (0, 7));
+    h.keys([shift(KeyCode::Down), ctrl(KeyCode::Char('j'))]);
+    assert_eq!(h.text(), "let x = 1 + 2;");
+}
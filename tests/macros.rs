@@ -0,0 +1,107 @@
+mod harness;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use harness::{ctrl, key, shift, temp_dir, Harness};
+use text_editor::macros::{key_name, parse_key, Macros};
+
+/// record `keys` into register `name`
+fn record(h: &mut Harness, name: &str, keys: impl IntoIterator<Item = KeyEvent>) {
+    h.press(ctrl(KeyCode::Char('r')));
+    h.type_str(name).press(key(KeyCode::Enter));
+    h.keys(keys);
+    h.press(ctrl(KeyCode::Char('r')));
+}
+
+fn play(h: &mut Harness, args: &str) {
+    h.press(ctrl(KeyCode::Char('t')));
+    h.type_str(args).press(key(KeyCode::Enter));
+}
+
+#[test]
+fn record_and_play_a_number_of_times() {
+    let mut h = Harness::new("a\nb\nc\nd");
+    record(
+        &mut h,
+        "dash",
+        [key(KeyCode::Char('-')), key(KeyCode::Home), key(KeyCode::Down)],
+    );
+    assert_eq!(h.text(), "-a\nb\nc\nd");
+    assert!(h.data.macros.recording.is_none());
+    play(&mut h, "dash 2");
+    assert_eq!(h.text(), "-a\n-b\n-c\nd");
+    // no name plays the last recorded macro once
+    play(&mut h, "");
+    assert_eq!(h.text(), "-a\n-b\n-c\n-d");
+}
+
+#[test]
+fn star_plays_until_the_end_of_the_buffer() {
+    let mut h = Harness::new("x,1\ny,2\nz,3");
+    record(
+        &mut h,
+        "",
+        [
+            key(KeyCode::End),
+            key(KeyCode::Char(';')),
+            key(KeyCode::Home),
+            key(KeyCode::Down),
+        ],
+    );
+    play(&mut h, "default *");
+    assert_eq!(h.text(), "x,1;\ny,2;\nz,3;");
+    // recording did the first line
+    assert_eq!(h.data.message.show(), Some("Played macro default 2 times"));
+}
+
+#[test]
+fn star_stops_for_a_macro_adding_lines() {
+    let mut h = Harness::new("a\nb\nc");
+    record(&mut h, "", [key(KeyCode::End), key(KeyCode::Enter)]);
+    assert_eq!(h.text(), "a\n\nb\nc");
+    // one run for each of the lines from the cursor down when playing started
+    play(&mut h, "default *");
+    assert_eq!(h.text(), "a\n\n\n\n\nb\nc");
+    assert_eq!(h.data.message.show(), Some("Played macro default 3 times"));
+}
+
+#[test]
+fn unknown_macro_is_reported() {
+    let mut h = Harness::new("a");
+    play(&mut h, "nope");
+    assert_eq!(h.data.message.show(), Some("No macro named \"nope\""));
+    assert_eq!(h.text(), "a");
+}
+
+#[test]
+fn key_names_round_trip() {
+    let keys = [
+        key(KeyCode::Char('a')),
+        key(KeyCode::Char('+')),
+        key(KeyCode::Char(' ')),
+        ctrl(KeyCode::Char('+')),
+        shift(KeyCode::Left),
+        KeyEvent::new(KeyCode::PageDown, KeyModifiers::CONTROL | KeyModifiers::ALT),
+        key(KeyCode::F(5)),
+    ];
+    for ke in keys {
+        let name = key_name(&ke).unwrap();
+        assert_eq!(parse_key(&name), Some(ke), "{}", name);
+    }
+    assert_eq!(key_name(&ctrl(KeyCode::Char('s'))).unwrap(), "Ctrl+s");
+    assert_eq!(parse_key("Ctrl+Nope"), None);
+}
+
+#[test]
+fn registers_persist_in_their_file() {
+    let dir = temp_dir("macros");
+    let path = dir.join("macros.toml");
+    let mut h = Harness::new("a");
+    h.data.macros = Macros::load(path.clone()).unwrap();
+    record(&mut h, "greet", [shift(KeyCode::Char('H')), key(KeyCode::Char('i'))]);
+    let loaded = Macros::load(path).unwrap();
+    assert_eq!(
+        loaded.get("greet"),
+        Some(&[shift(KeyCode::Char('H')), key(KeyCode::Char('i'))][..])
+    );
+    assert_eq!(loaded.last.as_deref(), Some("greet"));
+}
@@ -0,0 +1,50 @@
+mod harness;
+
+use crossterm::event::KeyCode;
+use harness::{ctrl, key, Harness};
+
+fn numbered(n: usize) -> String {
+    (0..n).map(|i| format!("line {}", i)).collect::<Vec<_>>().join("\n")
+}
+
+#[test]
+fn home_toggles_between_indent_and_column_zero() {
+    let mut h = Harness::new("    let x;");
+    h.press(key(KeyCode::End));
+    h.press(key(KeyCode::Home));
+    assert_eq!(h.cursor(), (0, 4));
+    h.press(key(KeyCode::Home));
+    assert_eq!(h.cursor(), (0, 0));
+    h.press(key(KeyCode::Home));
+    assert_eq!(h.cursor(), (0, 4));
+}
+
+#[test]
+fn ctrl_home_and_end_jump_to_buffer_ends() {
+    let mut h = Harness::new(&numbered(30));
+    h.press(ctrl(KeyCode::End));
+    assert_eq!(h.cursor(), (29, 7));
+    assert_eq!(h.data.top_visible, 22);
+    h.press(ctrl(KeyCode::Home));
+    assert_eq!(h.cursor(), (0, 0));
+    assert_eq!(h.data.top_visible, 0);
+}
+
+#[test]
+fn page_keys_move_by_text_height_and_scroll() {
+    // 10 rows leave 8 for text
+    let mut h = Harness::new(&numbered(30));
+    h.keys([key(KeyCode::Down), key(KeyCode::Down), key(KeyCode::PageDown)]);
+    assert_eq!(h.cursor(), (10, 0));
+    assert_eq!(h.data.top_visible, 8);
+    assert_eq!(h.frame()[3], "010| line 10");
+    h.press(key(KeyCode::PageDown));
+    h.press(key(KeyCode::PageDown));
+    assert_eq!(h.cursor(), (26, 0));
+    assert_eq!(h.data.top_visible, 22);
+    h.press(key(KeyCode::PageDown));
+    assert_eq!(h.cursor(), (29, 0));
+    h.press(key(KeyCode::PageUp));
+    assert_eq!(h.cursor(), (21, 0));
+    assert_eq!(h.data.top_visible, 14);
+}
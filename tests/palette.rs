@@ -0,0 +1,63 @@
+mod harness;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use harness::{key, Harness};
+use text_editor::{commands::COMMANDS, data::PromptType};
+
+fn selected(h: &Harness) -> &'static str {
+    let list = h.data.prompt.as_ref().unwrap().list.as_ref().unwrap();
+    COMMANDS[list.selected_item().unwrap()].name
+}
+
+#[test]
+fn f1_runs_the_picked_command() {
+    let mut h = Harness::new("abc");
+    h.press(key(KeyCode::F(1)));
+    h.type_str("dupl");
+    assert_eq!(selected(&h), "Duplicate lines");
+    h.press(key(KeyCode::Enter));
+    assert!(h.data.prompt.is_none());
+    assert_eq!(h.text(), "abc\nabc");
+}
+
+#[test]
+fn ctrl_shift_p_opens_the_palette_with_key_bindings() {
+    let mut h = Harness::with_size("abc", 8, 40);
+    h.press(KeyEvent::new(
+        KeyCode::Char('P'),
+        KeyModifiers::CONTROL | KeyModifiers::SHIFT,
+    ));
+    assert_eq!(h.data.prompt.as_ref().unwrap().p_type, PromptType::Command);
+    h.type_str("tgc");
+    assert_eq!(selected(&h), "Toggle comment");
+    let frame = h.frame();
+    let list = h.data.prompt.as_ref().unwrap().list.as_ref().unwrap();
+    // the best match comes first, the last one sits right above the prompt line
+    assert_eq!(frame[7 - list.shown.len()], " Toggle comment                  Ctrl+/");
+    assert_eq!(frame[7], "Command:  tgc");
+}
+
+#[test]
+fn arrows_wrap_around_and_esc_closes() {
+    let mut h = Harness::new("abc");
+    h.press(key(KeyCode::F(1)));
+    assert_eq!(selected(&h), COMMANDS[0].name);
+    h.press(key(KeyCode::Up));
+    assert_eq!(selected(&h), COMMANDS[COMMANDS.len() - 1].name);
+    h.press(key(KeyCode::Down));
+    h.press(key(KeyCode::Down));
+    assert_eq!(selected(&h), COMMANDS[1].name);
+    h.press(key(KeyCode::Esc));
+    assert!(h.data.prompt.is_none());
+    assert_eq!(h.text(), "abc");
+}
+
+#[test]
+fn no_match_runs_nothing() {
+    let mut h = Harness::new("abc");
+    h.press(key(KeyCode::F(1)));
+    h.type_str("zzzz");
+    assert!(h.frame().iter().any(|r| r.trim() == "No matches"));
+    h.press(key(KeyCode::Enter));
+    assert_eq!(h.text(), "abc");
+}
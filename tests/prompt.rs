@@ -0,0 +1,86 @@
+mod harness;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use harness::{ctrl, key, temp_dir, Harness};
+use text_editor::{data::PromptType, prompt_history::PromptHistory};
+
+fn prompt_text(h: &Harness) -> (String, usize) {
+    let p = h.data.prompt.as_ref().unwrap();
+    (p.data.data.clone(), p.cursor)
+}
+
+#[test]
+fn readline_keys_edit_the_prompt_not_the_buffer() {
+    let mut h = Harness::new("");
+    h.press(ctrl(KeyCode::Char('g')));
+    h.type_str("s/big old/new");
+    h.press(ctrl(KeyCode::Char('w')));
+    assert_eq!(prompt_text(&h), ("s/big ".to_owned(), 6));
+    h.keys([ctrl(KeyCode::Left), ctrl(KeyCode::Left)]);
+    assert_eq!(prompt_text(&h).1, 1);
+    h.press(ctrl(KeyCode::Char('k')));
+    assert_eq!(prompt_text(&h), ("s".to_owned(), 1));
+    h.type_str("ort");
+    h.keys([ctrl(KeyCode::Char('a')), key(KeyCode::Char('x'))]);
+    assert_eq!(prompt_text(&h), ("xsort".to_owned(), 1));
+    // Ctrl+E is the end of the prompt here, not the encoding prompt
+    h.press(ctrl(KeyCode::Char('e')));
+    assert_eq!(h.data.prompt.as_ref().unwrap().p_type, PromptType::CommandLine);
+    assert_eq!(prompt_text(&h).1, 5);
+    h.keys([key(KeyCode::Left), ctrl(KeyCode::Char('u'))]);
+    assert_eq!(prompt_text(&h), ("t".to_owned(), 0));
+    h.keys([key(KeyCode::End), KeyEvent::new(KeyCode::Char('b'), KeyModifiers::ALT)]);
+    assert_eq!(prompt_text(&h), ("t".to_owned(), 0));
+}
+
+#[test]
+fn search_is_filled_with_the_word_at_the_cursor() {
+    let mut h = Harness::new("alpha beta\nbeta");
+    h.keys([key(KeyCode::Right), key(KeyCode::Right), key(KeyCode::Right)]);
+    h.keys([key(KeyCode::Right), key(KeyCode::Right), key(KeyCode::Right)]);
+    h.press(ctrl(KeyCode::Char('f')));
+    assert_eq!(prompt_text(&h), ("beta".to_owned(), 4));
+    h.press(key(KeyCode::Enter));
+    // the search starts after the cursor, so it moves on to the next beta
+    assert_eq!(h.cursor(), (1, 0));
+    // typing replaces the filled in text, other keys keep it
+    h.press(ctrl(KeyCode::Char('f')));
+    h.type_str("al");
+    assert_eq!(prompt_text(&h).0, "al");
+    h.press(key(KeyCode::Esc));
+    h.keys([ctrl(KeyCode::Char('f')), key(KeyCode::End)]);
+    h.type_str("!");
+    assert_eq!(prompt_text(&h).0, "beta!");
+}
+
+#[test]
+fn empty_spot_fills_search_with_the_last_term() {
+    let mut h = Harness::new("one two\n\none");
+    h.press(ctrl(KeyCode::Char('f')));
+    h.press(ctrl(KeyCode::Char('u')));
+    h.type_str("two").press(key(KeyCode::Enter));
+    h.keys([key(KeyCode::Down)]);
+    h.press(ctrl(KeyCode::Char('f')));
+    assert_eq!(prompt_text(&h).0, "two");
+}
+
+#[test]
+fn history_is_kept_per_prompt_type_across_sessions() {
+    let dir = temp_dir("history");
+    let path = dir.join("history.toml");
+    let mut h = Harness::new("");
+    h.data.prompt_history = PromptHistory::load(path.clone()).unwrap();
+    h.press(ctrl(KeyCode::Char('f')));
+    h.type_str("needle").press(key(KeyCode::Enter));
+    h.press(ctrl(KeyCode::Char('g')));
+    h.type_str("goto 0").press(key(KeyCode::Enter));
+
+    let mut h = Harness::new("");
+    h.data.prompt_history = PromptHistory::load(path).unwrap();
+    h.keys([ctrl(KeyCode::Char('g')), key(KeyCode::Up)]);
+    assert_eq!(prompt_text(&h).0, "goto 0");
+    h.press(key(KeyCode::Up));
+    assert_eq!(prompt_text(&h).0, "goto 0");
+    h.keys([key(KeyCode::Esc), ctrl(KeyCode::Char('f')), key(KeyCode::Up)]);
+    assert_eq!(prompt_text(&h).0, "needle");
+}
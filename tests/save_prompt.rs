@@ -0,0 +1,98 @@
+mod harness;
+
+use std::{fs, path::PathBuf};
+
+use crossterm::event::KeyCode;
+use harness::{ctrl, key, temp_dir, Harness};
+use text_editor::{data::PromptType, paths};
+
+fn prompt_text(h: &Harness) -> String {
+    h.data.prompt.as_ref().unwrap().data.data.clone()
+}
+
+#[test]
+fn tab_completes_and_lists_directory_entries() {
+    let dir = temp_dir("save-complete");
+    fs::create_dir(dir.join("alpine")).unwrap();
+    fs::write(dir.join("alpha.txt"), "").unwrap();
+    fs::write(dir.join("beta.txt"), "").unwrap();
+    fs::write(dir.join(".hidden"), "").unwrap();
+    let base = dir.display().to_string();
+    let mut h = Harness::new("text");
+    h.press(ctrl(KeyCode::Char('s')));
+    h.type_str(&format!("{}/al", base));
+    h.press(key(KeyCode::Tab));
+    assert_eq!(prompt_text(&h), format!("{}/alp", base));
+    let labels: Vec<String> = {
+        let list = h.data.prompt.as_ref().unwrap().list.as_ref().unwrap();
+        list.items.iter().map(|i| i.label.clone()).collect()
+    };
+    assert_eq!(labels, ["alpha.txt", "alpine/"]);
+    // typing narrows the list, Tab takes the selected entry
+    h.type_str("i");
+    assert_eq!(h.data.prompt.as_ref().unwrap().list.as_ref().unwrap().items.len(), 1);
+    h.press(key(KeyCode::Tab));
+    assert_eq!(prompt_text(&h), format!("{}/alpine/", base));
+    assert!(h.data.prompt.as_ref().unwrap().list.is_none());
+    assert_eq!(paths::candidates(&dir, ""), ["alpha.txt", "alpine/", "beta.txt"]);
+    assert_eq!(paths::candidates(&dir, "."), [".hidden"]);
+}
+
+#[test]
+fn overwriting_a_file_asks_first() {
+    let dir = temp_dir("save-overwrite");
+    let path = dir.join("taken.txt");
+    fs::write(&path, "old").unwrap();
+    let mut h = Harness::new("new");
+    h.press(ctrl(KeyCode::Char('s')));
+    h.type_str(&path.display().to_string());
+    h.press(key(KeyCode::Enter));
+    assert_eq!(h.data.prompt.as_ref().unwrap().p_type, PromptType::Confirm);
+    h.press(key(KeyCode::Char('n')));
+    assert!(h.data.prompt.is_none());
+    assert_eq!(fs::read_to_string(&path).unwrap(), "old");
+    h.press(ctrl(KeyCode::Char('s')));
+    h.type_str(&path.display().to_string());
+    h.keys([key(KeyCode::Enter), key(KeyCode::Char('y'))]);
+    assert_eq!(fs::read_to_string(&path).unwrap().trim_end(), "new");
+    assert_eq!(h.data.path, path);
+}
+
+#[test]
+fn missing_directories_are_created_after_asking() {
+    let dir = temp_dir("save-mkdir");
+    let path = dir.join("a/b/file.txt");
+    let mut h = Harness::new("text");
+    h.press(ctrl(KeyCode::Char('s')));
+    h.type_str(&path.display().to_string());
+    h.press(key(KeyCode::Enter));
+    assert!(prompt_text(&h).is_empty());
+    assert_eq!(h.data.prompt.as_ref().unwrap().p_type, PromptType::Confirm);
+    h.press(key(KeyCode::Char('y')));
+    assert_eq!(fs::read_to_string(&path).unwrap().trim_end(), "text");
+}
+
+#[test]
+fn open_starts_in_the_directory_of_the_file() {
+    let dir = temp_dir("open-relative");
+    fs::write(dir.join("first.txt"), "first").unwrap();
+    fs::write(dir.join("second.txt"), "second").unwrap();
+    let mut h = Harness::new("");
+    h.press(ctrl(KeyCode::Char('g')));
+    h.type_str(&format!("e {}", dir.join("first.txt").display()));
+    h.press(key(KeyCode::Enter));
+    h.press(ctrl(KeyCode::Char('o')));
+    h.type_str("sec");
+    h.keys([key(KeyCode::Tab), key(KeyCode::Enter)]);
+    assert_eq!(h.text(), "second");
+    assert_eq!(h.data.path, dir.join("second.txt"));
+}
+
+#[test]
+fn tilde_is_the_home_directory() {
+    let home = directories::BaseDirs::new().unwrap().home_dir().to_path_buf();
+    assert_eq!(paths::expand_home("~"), home);
+    assert_eq!(paths::expand_home("~/notes"), home.join("notes"));
+    assert_eq!(paths::expand_home("a/~"), PathBuf::from("a/~"));
+    assert_eq!(paths::resolve(&PathBuf::from("/tmp"), "~/x"), home.join("x"));
+}
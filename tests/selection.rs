@@ -0,0 +1,104 @@
+mod harness;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use harness::{ctrl, key, shift, Harness};
+
+fn ctrl_shift(code: KeyCode) -> KeyEvent {
+    KeyEvent::new(code, KeyModifiers::CONTROL | KeyModifiers::SHIFT)
+}
+
+#[test]
+fn shift_arrows_select_and_typing_replaces() {
+    let mut h = Harness::new("hello world");
+    h.keys([ctrl_shift(KeyCode::Right)]);
+    assert!(h.data.selection().is_some());
+    h.type_str("bye");
+    assert_eq!(h.text(), "bye world");
+    assert_eq!(h.data.selection(), None);
+    h.keys([shift(KeyCode::Home), key(KeyCode::Backspace)]);
+    assert_eq!(h.text(), " world");
+    assert_eq!(h.cursor(), (0, 0));
+}
+
+#[test]
+fn selection_is_highlighted() {
+    let mut h = Harness::new("abc\ndef");
+    h.keys([key(KeyCode::Right), shift(KeyCode::Down)]);
+    let mut grid = text_editor::screen::Grid::new(40, 10);
+    text_editor::render::draw(&h.data, &h.config, &mut grid);
+    let selected = text_editor::render::selection_style();
+    let styled = |row: usize| -> Vec<bool> {
+        grid.row(row)[5..10]
+            .iter()
+            .map(|c| c.style == selected)
+            .collect()
+    };
+    // "abc" from column 1 plus the line break, then "d"
+    assert_eq!(styled(1), [false, true, true, true, false]);
+    assert_eq!(styled(2), [true, false, false, false, false]);
+}
+
+#[test]
+fn tab_indents_selected_lines_and_keeps_selection() {
+    let mut h = Harness::new("a\n\nb\nc");
+    h.keys([
+        key(KeyCode::Right),
+        shift(KeyCode::Down),
+        shift(KeyCode::Down),
+        shift(KeyCode::End),
+        key(KeyCode::Tab),
+    ]);
+    assert_eq!(h.text(), "    a\n\n    b\nc");
+    assert_eq!(h.cursor(), (2, 5));
+    assert_eq!(h.data.anchor.map(|a| (a.0, a.1)), Some((0, 5)));
+    h.press(shift(KeyCode::BackTab));
+    assert_eq!(h.text(), "a\n\nb\nc");
+    assert_eq!(h.cursor(), (2, 1));
+    h.keys([key(KeyCode::Tab), key(KeyCode::Tab), ctrl(KeyCode::Char('z'))]);
+    assert_eq!(h.text(), "    a\n\n    b\nc");
+    assert!(h.data.selection().is_some());
+}
+
+#[test]
+fn selection_ending_at_column_zero_skips_last_line() {
+    let mut h = Harness::new("a\nb\nc");
+    h.keys([shift(KeyCode::Down), shift(KeyCode::Down), key(KeyCode::Tab)]);
+    assert_eq!(h.text(), "    a\n    b\nc");
+}
+
+#[test]
+fn undo_and_redo() {
+    let mut h = Harness::new("x");
+    h.type_str("ab cd");
+    h.press(key(KeyCode::Enter));
+    assert_eq!(h.text(), "ab cd\nx");
+    h.press(ctrl(KeyCode::Char('z')));
+    assert_eq!(h.text(), "ab cdx");
+    // consecutive typing is one step
+    h.press(ctrl(KeyCode::Char('z')));
+    assert_eq!(h.text(), "x");
+    assert_eq!(h.cursor(), (0, 0));
+    h.press(ctrl(KeyCode::Char('z')));
+    assert_eq!(h.data.message.text, "Nothing to undo");
+    h.keys([ctrl(KeyCode::Char('y')), ctrl(KeyCode::Char('y'))]);
+    assert_eq!(h.text(), "ab cd\nx");
+    assert_eq!(h.cursor(), (1, 0));
+}
+
+#[test]
+fn use_tabs_indents_selected_lines_with_tabs_only() {
+    let mut h = Harness::new("a\n    b\nc\n    d");
+    h.config.use_tabs = true;
+    h.keys([
+        shift(KeyCode::Down),
+        shift(KeyCode::Down),
+        shift(KeyCode::End),
+        key(KeyCode::Tab),
+    ]);
+    // the spaces of lines outside the selection are left as they are
+    assert_eq!(h.text(), "\ta\n\t    b\n\tc\n    d");
+    h.press(shift(KeyCode::BackTab));
+    assert_eq!(h.text(), "a\n    b\nc\n    d");
+    h.keys([key(KeyCode::Home), key(KeyCode::Tab)]);
+    assert_eq!(h.text(), "a\n    b\n\tc\n    d");
+}
@@ -0,0 +1,76 @@
+mod harness;
+
+use std::fs;
+
+use crossterm::event::KeyCode;
+use harness::{ctrl, key, temp_dir, Harness, TempDir};
+use text_editor::{
+    editor::restore_session,
+    explorer::Explorer,
+    session::Session,
+};
+
+/// a folder with two files of a few lines
+fn files(name: &str) -> TempDir {
+    let dir = temp_dir(name);
+    fs::write(dir.join("a.txt"), "one\ntwo\nthree\nfour").unwrap();
+    fs::write(dir.join("b.txt"), "alpha\nbeta").unwrap();
+    dir
+}
+
+fn edit(h: &mut Harness, path: &std::path::Path) {
+    h.press(ctrl(KeyCode::Char('g')));
+    h.type_str(&format!("e {}", path.display()));
+    h.press(key(KeyCode::Enter));
+}
+
+#[test]
+fn a_file_opens_where_it_was_left() {
+    let dir = files("session-place");
+    let mut h = Harness::new("");
+    edit(&mut h, &dir.join("a.txt"));
+    h.keys([key(KeyCode::Down), key(KeyCode::Down), key(KeyCode::End)]);
+    edit(&mut h, &dir.join("b.txt"));
+    assert_eq!(h.cursor(), (0, 0));
+    edit(&mut h, &dir.join("a.txt"));
+    assert_eq!(h.cursor(), (2, 5));
+}
+
+#[test]
+fn a_shorter_file_keeps_the_cursor_inside() {
+    let dir = files("session-shorter");
+    let mut h = Harness::new("");
+    edit(&mut h, &dir.join("a.txt"));
+    h.keys([ctrl(KeyCode::End), key(KeyCode::End)]);
+    assert_eq!(h.cursor(), (3, 4));
+    edit(&mut h, &dir.join("b.txt"));
+    fs::write(dir.join("a.txt"), "one").unwrap();
+    edit(&mut h, &dir.join("a.txt"));
+    assert_eq!(h.cursor(), (0, 3));
+}
+
+#[test]
+fn restore_brings_back_the_open_buffers() {
+    let dir = files("session-restore");
+    let store = dir.join("session.toml");
+    let mut h = Harness::new("");
+    h.data.session = Session::load(store.clone()).unwrap();
+    edit(&mut h, &dir.join("b.txt"));
+    h.press(key(KeyCode::Down));
+    // the explorer opens files in buffers of their own
+    h.data.explorer = Some(Explorer::new(dir.to_path_buf()));
+    h.press(key(KeyCode::Enter));
+    assert_eq!(h.data.path, dir.join("a.txt"));
+    h.keys([key(KeyCode::Down), key(KeyCode::Right)]);
+    Session::save(&mut h.data).unwrap();
+
+    let mut next = Harness::new("");
+    next.data.session = Session::load(store).unwrap();
+    restore_session(&mut next.data, &mut next.config);
+    assert_eq!(next.data.path, dir.join("a.txt"));
+    assert_eq!(next.cursor(), (1, 1));
+    assert_eq!(next.data.buffers.len(), 1);
+    next.press(ctrl(KeyCode::PageDown));
+    assert_eq!(next.data.path, dir.join("b.txt"));
+    assert_eq!(next.cursor(), (1, 0));
+}